@@ -102,7 +102,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .await?;
         light
             .breathing(
-                &GenericRGB {},
+                &GenericRGB::default(),
                 &ColorOption::RGB(0, 255, 0),
                 &SWAnimationRepeat::FiniteCount(2),
                 &SWAnimationSpeed::Fastest,
@@ -110,7 +110,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .await?;
         light
             .breathing(
-                &GenericRGB {},
+                &GenericRGB::default(),
                 &ColorOption::RGB(0, 0, 255),
                 &SWAnimationRepeat::FiniteCount(2),
                 &SWAnimationSpeed::Fastest,
@@ -60,7 +60,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         println!("SW Animation - Breathing effect...");
         light
             .breathing(
-                &GenericRGB {},
+                &GenericRGB::default(),
                 &ColorOption::RGB(255, 0, 0),
                 &SWAnimationRepeat::FiniteCount(2),
                 &SWAnimationSpeed::Fastest,
@@ -68,7 +68,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .await?;
         light
             .breathing(
-                &GenericRGB {},
+                &GenericRGB::default(),
                 &ColorOption::RGB(0, 255, 0),
                 &SWAnimationRepeat::FiniteCount(2),
                 &SWAnimationSpeed::Fastest,
@@ -76,7 +76,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .await?;
         light
             .breathing(
-                &GenericRGB {},
+                &GenericRGB::default(),
                 &ColorOption::RGB(0, 0, 255),
                 &SWAnimationRepeat::FiniteCount(2),
                 &SWAnimationSpeed::Fastest,
@@ -64,13 +64,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
         // choose the characteristic to use to write to the device
         let chosen = light.characteristics_by_type(char_kind_filter).unwrap();
-        println!("\nChosen {:?}\n", chosen.get(0));
+        println!("\nChosen {:?}\n", chosen.first());
 
         // set it as a write_char for the current device
         // you can set different write characteristics for different
         // devices, as one controller support devices with different communication
         // protocols
-        light.set_write_char(&chosen.get(0).unwrap());
+        light.set_write_char(chosen.first().unwrap());
 
         /////////////////////////////////
         // Control the lights as usual //
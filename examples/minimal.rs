@@ -23,7 +23,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     controller.set_all_char(&CharKind::Write, &UuidKind::Uuid16(0xFFD9))?;
 
     // Setting first found light color to red
-    let first_light = controller.list().get(0).unwrap();
+    let first_light = controller.list().first().unwrap();
     first_light.color(&protocol, 255, 0, 0).await?;
 
     Ok(())
@@ -1,16 +1,28 @@
-use crate::capability::color::ColorOption;
-use crate::communication_protocol::Protocol;
-use crate::device::Device;
+use crate::capability::color::{device_color, ColorOption};
+use crate::communication_protocol::{CommandKind, Protocol};
 use crate::device::Write;
-use crate::error::{BluetoothError};
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use crate::units::Level;
 use async_trait::async_trait;
 
 //------------//
 // Brightness //
 //------------//
+// scaled by `Level` until a color is set
+const WHITE: ColorOption = ColorOption::RGB(255, 255, 255);
+
+/// Brightness level, optionally of a given color.
+///
+/// `Level` keeps the current color: protocols without a native
+/// brightness command (see `Protocol::native_brightness`) encode it as the
+/// last color set on the device (`Device::state_cache`), scaled.
+/// `LevelWithColor` writes the full color then the native level on
+/// protocols that have one, the scaled color otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BrightnessOption<'e> {
-    Level(f32),
-    LevelWithColor(f32, &'e ColorOption),
+    Level(Level),
+    LevelWithColor(Level, &'e ColorOption),
 }
 #[async_trait]
 pub trait Brightness {
@@ -30,7 +42,12 @@ pub trait Brightness {
         r: u8,
         g: u8,
         b: u8,
-        level: f32,
+        level: Level,
+    ) -> Result<(), BluetoothError>;
+    async fn set_brightness<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        level: Level,
     ) -> Result<(), BluetoothError>;
 }
 
@@ -45,7 +62,10 @@ impl<D: Device + std::marker::Sync> Brightness for D {
         protocol: &'e P,
         option: &'e BrightnessOption,
     ) -> Result<(), BluetoothError> {
-        device.push(&protocol.brightness(option)[..]).await?;
+        for (command, frame) in brightness_commands(device, protocol, option) {
+            device.push_command(protocol, &command, &frame[..]).await?;
+        }
+        _track_brightness(device, protocol, option);
         Ok(())
     }
 
@@ -59,16 +79,83 @@ impl<D: Device + std::marker::Sync> Brightness for D {
         r: u8,
         g: u8,
         b: u8,
-        level: f32,
+        level: Level,
+    ) -> Result<(), BluetoothError> {
+        Brightness::set(
+            self,
+            protocol,
+            &BrightnessOption::LevelWithColor(level, &ColorOption::RGB(r, g, b)),
+        )
+        .await
+    }
+    async fn set_brightness<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        level: Level,
     ) -> Result<(), BluetoothError> {
-        self
-            .push(
-                &protocol.brightness(&BrightnessOption::LevelWithColor(
-                    level,
-                    &ColorOption::RGB(r, g, b),
-                ))[..],
+        Brightness::set(self, protocol, &BrightnessOption::Level(level)).await
+    }
+}
+
+// A level scaling the color shows it steadily, replacing any animation
+fn _track_brightness<D: Device, P: Protocol>(device: &D, protocol: &P, option: &BrightnessOption) {
+    device.state_cache().update(|state| match option {
+        BrightnessOption::Level(level) => {
+            state.brightness = Some(*level);
+            if !protocol.native_brightness() {
+                state.color.get_or_insert(WHITE);
+                state.mode = Some(LightMode::Color);
+            }
+        }
+        BrightnessOption::LevelWithColor(level, color) => {
+            state.color = Some(**color);
+            state.brightness = Some(*level);
+            state.mode = Some(LightMode::Color);
+        }
+    });
+}
+
+// Encodes a brightness option as the commands to push, with the channel
+// layout and calibration of the device; only the levels scaling the color
+// are calibrated, as the color they scale
+pub(crate) fn brightness_commands<D: Device, P: Protocol>(
+    device: &D,
+    protocol: &P,
+    option: &BrightnessOption,
+) -> Vec<(CommandKind, Vec<u8>)> {
+    match option {
+        BrightnessOption::Level(_) if protocol.native_brightness() => {
+            vec![(CommandKind::Brightness, protocol.brightness(option))]
+        }
+        // scales the last color, white until one is set
+        BrightnessOption::Level(level) => {
+            let color = device.state_cache().color().unwrap_or(WHITE);
+            brightness_commands(
+                device,
+                protocol,
+                &BrightnessOption::LevelWithColor(*level, &color),
             )
-            .await?;
-        Ok(())
+        }
+        // the full color, then the native level, each one routed on its own
+        // so that the level is not applied twice
+        BrightnessOption::LevelWithColor(level, color) if protocol.native_brightness() => vec![
+            (
+                CommandKind::Color,
+                protocol.color(&device_color(device, protocol, color)),
+            ),
+            (
+                CommandKind::Brightness,
+                protocol.brightness(&BrightnessOption::Level(*level)),
+            ),
+        ],
+        // scaled before the calibration, so that it applies to the
+        // values actually written, minimum level included
+        BrightnessOption::LevelWithColor(level, color) => vec![(
+            CommandKind::Brightness,
+            protocol.brightness(&BrightnessOption::LevelWithColor(
+                Level::FULL,
+                &device_color(device, protocol, &color.scaled(*level)),
+            )),
+        )],
     }
 }
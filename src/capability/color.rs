@@ -1,14 +1,171 @@
-use crate::communication_protocol::Protocol;
-use crate::device::Device;
+use crate::communication_protocol::{CommandKind, Protocol};
 use crate::device::Write;
-use crate::error::{BluetoothError};
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use crate::units::Level;
 use async_trait::async_trait;
 
 //-------//
 // Color //
 //-------//
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorOption {
     RGB(u8, u8, u8),
+    /// RGB plus a white channel
+    RGBW(u8, u8, u8, u8),
+    /// RGB plus warm and cold white channels
+    RGBWW(u8, u8, u8, u8, u8),
+}
+
+/// Color channels driven by a controller, see `Protocol::channel_layout`;
+/// colors are converted to the layout of the protocol before encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelLayout {
+    #[default]
+    RGB,
+    RGBW,
+    RGBWW,
+}
+
+// tint of the white channels, when blended into RGB
+const WARM_WHITE_KELVIN: u16 = 2700;
+const COLD_WHITE_KELVIN: u16 = 6500;
+
+impl ColorOption {
+    /// Approximation of the color of a blackbody at the given
+    /// temperature (Tanner Helland), clamped to `1000..=40000` Kelvin.
+    pub fn from_kelvin(kelvin: u16) -> ColorOption {
+        let t = kelvin.clamp(1000, 40000) as f32 / 100.0;
+        let r = if t <= 66.0 {
+            255.0
+        } else {
+            329.698_73 * (t - 60.0).powf(-0.133_204_76)
+        };
+        let g = if t <= 66.0 {
+            99.470_8 * t.ln() - 161.119_57
+        } else {
+            288.122_16 * (t - 60.0).powf(-0.075_514_846)
+        };
+        let b = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (t - 10.0).ln() - 305.044_8
+        };
+        ColorOption::RGB(
+            r.clamp(0.0, 255.0) as u8,
+            g.clamp(0.0, 255.0) as u8,
+            b.clamp(0.0, 255.0) as u8,
+        )
+    }
+
+    /// Red, green and blue channels, the white
+    /// channels being blended into them
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            ColorOption::RGB(r, g, b) => (r, g, b),
+            ColorOption::RGBW(r, g, b, w) => _blend((r, g, b), w, (255, 255, 255)),
+            ColorOption::RGBWW(r, g, b, warm, cold) => _blend(
+                _blend(
+                    (r, g, b),
+                    warm,
+                    ColorOption::from_kelvin(WARM_WHITE_KELVIN).rgb(),
+                ),
+                cold,
+                ColorOption::from_kelvin(COLD_WHITE_KELVIN).rgb(),
+            ),
+        }
+    }
+
+    /// Every channel scaled by `level`
+    pub fn scaled(&self, level: Level) -> ColorOption {
+        let scale = |c: u8| (c as f32 * level.get()) as u8;
+        match *self {
+            ColorOption::RGB(r, g, b) => ColorOption::RGB(scale(r), scale(g), scale(b)),
+            ColorOption::RGBW(r, g, b, w) => {
+                ColorOption::RGBW(scale(r), scale(g), scale(b), scale(w))
+            }
+            ColorOption::RGBWW(r, g, b, warm, cold) => {
+                ColorOption::RGBWW(scale(r), scale(g), scale(b), scale(warm), scale(cold))
+            }
+        }
+    }
+
+    /// Converts the color to a channel layout: the white shared by
+    /// the RGB channels is moved to the white channels (both of them
+    /// for `RGBWW`), white channels are blended into RGB otherwise.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::{ChannelLayout, ColorOption};
+    ///
+    /// let color = ColorOption::RGB(255, 180, 100);
+    /// assert_eq!(color.to_layout(ChannelLayout::RGBW), ColorOption::RGBW(155, 80, 0, 100));
+    /// assert_eq!(
+    ///     color.to_layout(ChannelLayout::RGBWW),
+    ///     ColorOption::RGBWW(155, 80, 0, 100, 100)
+    /// );
+    /// assert_eq!(ColorOption::RGBW(155, 80, 0, 100).to_layout(ChannelLayout::RGB), color);
+    /// ```
+    pub fn to_layout(&self, layout: ChannelLayout) -> ColorOption {
+        match (*self, layout) {
+            (ColorOption::RGB(..), ChannelLayout::RGB)
+            | (ColorOption::RGBW(..), ChannelLayout::RGBW)
+            | (ColorOption::RGBWW(..), ChannelLayout::RGBWW) => *self,
+            (ColorOption::RGB(r, g, b), ChannelLayout::RGBW) => {
+                let w = r.min(g).min(b);
+                ColorOption::RGBW(r - w, g - w, b - w, w)
+            }
+            (ColorOption::RGB(r, g, b), ChannelLayout::RGBWW) => {
+                let w = r.min(g).min(b);
+                ColorOption::RGBWW(r - w, g - w, b - w, w, w)
+            }
+            (ColorOption::RGBW(r, g, b, w), ChannelLayout::RGBWW) => {
+                ColorOption::RGBWW(r, g, b, w, w)
+            }
+            (ColorOption::RGBWW(r, g, b, warm, cold), ChannelLayout::RGBW) => {
+                ColorOption::RGBW(r, g, b, warm.max(cold))
+            }
+            (_, ChannelLayout::RGB) => {
+                let (r, g, b) = self.rgb();
+                ColorOption::RGB(r, g, b)
+            }
+        }
+    }
+}
+
+// adds a white channel of the given tint
+fn _blend((r, g, b): (u8, u8, u8), white: u8, (tr, tg, tb): (u8, u8, u8)) -> (u8, u8, u8) {
+    let add = |c: u8, tint: u8| c.saturating_add((white as u16 * tint as u16 / 255) as u8);
+    (add(r, tr), add(g, tg), add(b, tb))
+}
+
+// Color as written to the device: converted to the channel
+// layout of the protocol, calibrated, then reordered
+pub(crate) fn device_color<D: Device, P: Protocol>(
+    device: &D,
+    protocol: &P,
+    color: &ColorOption,
+) -> ColorOption {
+    device.channel_order().apply(
+        &device
+            .calibration()
+            .color(&color.to_layout(protocol.channel_layout())),
+    )
+}
+
+// Records a color written at full brightness; devices
+// with a native brightness keep their level
+fn _track_color<D: Device, P: Protocol>(device: &D, protocol: &P, color: &ColorOption) {
+    device.state_cache().update(|state| {
+        state.color = Some(*color);
+        if !protocol.native_brightness() {
+            state.brightness = Some(Level::FULL);
+        }
+        state.mode = Some(LightMode::Color);
+    });
 }
 #[async_trait]
 pub trait Color {
@@ -29,6 +186,12 @@ pub trait Color {
         g: u8,
         b: u8,
     ) -> Result<(), BluetoothError>;
+    /// Sets any color representation of `ble_ledly::color`
+    /// (or a parsed `ColorOption`, see its `FromStr`)
+    async fn set_color<'e, P, C>(&self, protocol: &'e P, color: C) -> Result<(), BluetoothError>
+    where
+        P: Protocol + std::marker::Send + std::marker::Sync,
+        C: Into<ColorOption> + std::marker::Send;
 }
 
 //-------------------------//
@@ -42,7 +205,14 @@ impl<D: Device + std::marker::Sync> Color for D {
         protocol: &'e P,
         option: &'e ColorOption,
     ) -> Result<(), BluetoothError> {
-        device.push(&protocol.color(option)[..]).await?;
+        device
+            .push_command(
+                protocol,
+                &CommandKind::Color,
+                &protocol.color(&device_color(device, protocol, option))[..],
+            )
+            .await?;
+        _track_color(device, protocol, option);
         Ok(())
     }
 
@@ -57,9 +227,13 @@ impl<D: Device + std::marker::Sync> Color for D {
         g: u8,
         b: u8,
     ) -> Result<(), BluetoothError> {
-        self
-            .push(&protocol.color(&ColorOption::RGB(r, g, b))[..])
-            .await?;
-        Ok(())
+        Color::set(self, protocol, &ColorOption::RGB(r, g, b)).await
+    }
+    async fn set_color<'e, P, C>(&self, protocol: &'e P, color: C) -> Result<(), BluetoothError>
+    where
+        P: Protocol + std::marker::Send + std::marker::Sync,
+        C: Into<ColorOption> + std::marker::Send,
+    {
+        Color::set(self, protocol, &color.into()).await
     }
 }
@@ -1,22 +1,78 @@
-use crate::communication_protocol::Protocol;
-use crate::device::Device;
+use std::fmt;
+use std::time::Duration;
+
+use crate::communication_protocol::{CommandKind, Protocol};
 use crate::device::Write;
-use crate::error::{BluetoothError};
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use crate::units::Level;
 use async_trait::async_trait;
 
 //---------//
 // animate //
 //---------//
+/// Built-in animations, the full _Triones_ mode range.
+///
+/// Not every protocol supports every animation, see
+/// `Protocol::hw_animations` for the ones a protocol encodes.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::hw_animate::{
+///     HWAnimateOption, HWAnimationSpeedSetting, HWBicolorOption, HWStaticColorOption,
+/// };
+///
+/// let speed = HWAnimationSpeedSetting::Speed5;
+/// let modes = HWAnimateOption::all(&speed);
+///
+/// assert_eq!(modes.len(), 20);
+/// assert_eq!(modes[0].to_string(), "Rainbow Pulsating");
+/// assert_eq!(
+///     HWAnimateOption::PulsatingBicolor(&HWBicolorOption::RedBlue, &speed).to_string(),
+///     "Pulsating Red/Blue"
+/// );
+/// assert_eq!(
+///     HWAnimateOption::Flashing(&HWStaticColorOption::Cyan, &speed).to_string(),
+///     "Flashing Cyan"
+/// );
+/// assert!(modes.iter().all(|mode| mode.speed() == &speed));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HWAnimateOption<'e> {
+    RainbowPulsating(&'e HWAnimationSpeedSetting),
     Pulsating(&'e HWStaticColorOption, &'e HWAnimationSpeedSetting),
+    PulsatingBicolor(&'e HWBicolorOption, &'e HWAnimationSpeedSetting),
+    RainbowFlashing(&'e HWAnimationSpeedSetting),
+    Flashing(&'e HWStaticColorOption, &'e HWAnimationSpeedSetting),
+    RainbowJumping(&'e HWAnimationSpeedSetting),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HWStaticColorOption {
     Red,
     Green,
     Blue,
+    Yellow,
+    Cyan,
+    Purple,
+    White,
+}
+
+/// Color pairs of `HWAnimateOption::PulsatingBicolor`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HWBicolorOption {
+    RedGreen,
+    RedBlue,
+    GreenBlue,
 }
-// TODO: more meaningf&ul name
+
+/// Speed of a built-in animation.
+///
+/// `Speed1` (slowest) to `Speed9` (fastest) are the hand-tuned steps of each
+/// protocol; `Level` and `Period` are mapped onto the native range of the
+/// controller, so the same setting gives a comparable speed across devices.
+/// `Protocol::animation_period` reports the period actually chosen.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HWAnimationSpeedSetting {
     Speed1,
     Speed2,
@@ -27,6 +83,189 @@ pub enum HWAnimationSpeedSetting {
     Speed7,
     Speed8,
     Speed9,
+    /// Normalized speed, from `0.0` (slowest) to `1.0` (fastest)
+    Level(Level),
+    /// Approximate duration of one animation cycle
+    Period(Duration),
+}
+
+/// Native speed range of a controller, with the approximate period
+/// of one animation cycle at both ends; periods in between are
+/// interpolated linearly over the native values.
+///
+/// ## Examples
+/// ```
+/// use std::time::Duration;
+/// use ble_ledly::capability::hw_animate::HWSpeedRange;
+/// use ble_ledly::units::Level;
+///
+/// let range = HWSpeedRange {
+///     slowest: 0x1F,
+///     fastest: 0x01,
+///     slowest_period: Duration::from_millis(6200),
+///     fastest_period: Duration::from_millis(200),
+/// };
+///
+/// assert_eq!(range.native_level(Level::OFF), 0x1F);
+/// assert_eq!(range.native_level(Level::FULL), 0x01);
+/// assert_eq!(range.native_period(Duration::from_secs(1)), 0x05);
+/// assert_eq!(range.native_period(Duration::from_secs(60)), 0x1F);
+/// assert_eq!(range.period(0x05), Duration::from_millis(1000));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HWSpeedRange {
+    pub slowest: u8,
+    pub fastest: u8,
+    pub slowest_period: Duration,
+    pub fastest_period: Duration,
+}
+
+impl HWSpeedRange {
+    /// Native value of a normalized speed
+    pub fn native_level(&self, level: Level) -> u8 {
+        self._native(level.get() as f64)
+    }
+
+    /// Native value whose period is the closest to `period`
+    pub fn native_period(&self, period: Duration) -> u8 {
+        let slowest = self.slowest_period.as_secs_f64();
+        let span = self.fastest_period.as_secs_f64() - slowest;
+        if span == 0.0 {
+            return self.slowest;
+        }
+        self._native(((period.as_secs_f64() - slowest) / span).clamp(0.0, 1.0))
+    }
+
+    /// Approximate period of a native value, to the millisecond
+    pub fn period(&self, native: u8) -> Duration {
+        let span = self.fastest as f64 - self.slowest as f64;
+        let position = if span == 0.0 {
+            0.0
+        } else {
+            ((native as f64 - self.slowest as f64) / span).clamp(0.0, 1.0)
+        };
+        let slowest = self.slowest_period.as_millis() as f64;
+        let fastest = self.fastest_period.as_millis() as f64;
+        Duration::from_millis((slowest + position * (fastest - slowest)).round() as u64)
+    }
+
+    // position from 0.0 (slowest) to 1.0 (fastest)
+    fn _native(&self, position: f64) -> u8 {
+        let span = self.fastest as f64 - self.slowest as f64;
+        (self.slowest as f64 + position * span).round() as u8
+    }
+}
+
+static STATIC_COLORS: [HWStaticColorOption; 7] = [
+    HWStaticColorOption::Red,
+    HWStaticColorOption::Green,
+    HWStaticColorOption::Blue,
+    HWStaticColorOption::Yellow,
+    HWStaticColorOption::Cyan,
+    HWStaticColorOption::Purple,
+    HWStaticColorOption::White,
+];
+
+static BICOLORS: [HWBicolorOption; 3] = [
+    HWBicolorOption::RedGreen,
+    HWBicolorOption::RedBlue,
+    HWBicolorOption::GreenBlue,
+];
+
+static SPEEDS: [HWAnimationSpeedSetting; 9] = [
+    HWAnimationSpeedSetting::Speed1,
+    HWAnimationSpeedSetting::Speed2,
+    HWAnimationSpeedSetting::Speed3,
+    HWAnimationSpeedSetting::Speed4,
+    HWAnimationSpeedSetting::Speed5,
+    HWAnimationSpeedSetting::Speed6,
+    HWAnimationSpeedSetting::Speed7,
+    HWAnimationSpeedSetting::Speed8,
+    HWAnimationSpeedSetting::Speed9,
+];
+
+impl<'e> HWAnimateOption<'e> {
+    /// Every animation at the given speed, in _Triones_ mode order
+    pub fn all(speed: &'e HWAnimationSpeedSetting) -> Vec<HWAnimateOption<'e>> {
+        let mut modes = vec![HWAnimateOption::RainbowPulsating(speed)];
+        modes.extend(
+            STATIC_COLORS
+                .iter()
+                .map(|color| HWAnimateOption::Pulsating(color, speed)),
+        );
+        modes.extend(
+            BICOLORS
+                .iter()
+                .map(|colors| HWAnimateOption::PulsatingBicolor(colors, speed)),
+        );
+        modes.push(HWAnimateOption::RainbowFlashing(speed));
+        modes.extend(
+            STATIC_COLORS
+                .iter()
+                .map(|color| HWAnimateOption::Flashing(color, speed)),
+        );
+        modes.push(HWAnimateOption::RainbowJumping(speed));
+        modes
+    }
+
+    pub fn speed(&self) -> &'e HWAnimationSpeedSetting {
+        match self {
+            HWAnimateOption::RainbowPulsating(speed)
+            | HWAnimateOption::Pulsating(_, speed)
+            | HWAnimateOption::PulsatingBicolor(_, speed)
+            | HWAnimateOption::RainbowFlashing(speed)
+            | HWAnimateOption::Flashing(_, speed)
+            | HWAnimateOption::RainbowJumping(speed) => speed,
+        }
+    }
+}
+
+impl HWStaticColorOption {
+    pub fn all() -> &'static [HWStaticColorOption] {
+        &STATIC_COLORS
+    }
+}
+
+impl HWBicolorOption {
+    pub fn all() -> &'static [HWBicolorOption] {
+        &BICOLORS
+    }
+}
+
+impl HWAnimationSpeedSetting {
+    /// Every speed step, slowest first
+    pub fn all() -> &'static [HWAnimationSpeedSetting] {
+        &SPEEDS
+    }
+}
+
+impl fmt::Display for HWAnimateOption<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HWAnimateOption::RainbowPulsating(_) => write!(f, "Rainbow Pulsating"),
+            HWAnimateOption::Pulsating(color, _) => write!(f, "Pulsating {}", color),
+            HWAnimateOption::PulsatingBicolor(colors, _) => write!(f, "Pulsating {}", colors),
+            HWAnimateOption::RainbowFlashing(_) => write!(f, "Rainbow Flashing"),
+            HWAnimateOption::Flashing(color, _) => write!(f, "Flashing {}", color),
+            HWAnimateOption::RainbowJumping(_) => write!(f, "Rainbow Jumping"),
+        }
+    }
+}
+
+impl fmt::Display for HWStaticColorOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl fmt::Display for HWBicolorOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HWBicolorOption::RedGreen => write!(f, "Red/Green"),
+            HWBicolorOption::RedBlue => write!(f, "Red/Blue"),
+            HWBicolorOption::GreenBlue => write!(f, "Green/Blue"),
+        }
+    }
 }
 
 #[async_trait]
@@ -45,7 +284,34 @@ pub trait HWAnimate {
         &self,
         protocol: &'e P,
         color: &'e HWStaticColorOption,
-        speed: &'e HWAnimationSpeedSetting
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError>;
+    async fn hw_anim_rainbow_pulsating<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError>;
+    async fn hw_anim_pulsating_bicolor<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        colors: &'e HWBicolorOption,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError>;
+    async fn hw_anim_rainbow_flashing<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError>;
+    async fn hw_anim_flashing<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        color: &'e HWStaticColorOption,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError>;
+    async fn hw_anim_rainbow_jumping<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        speed: &'e HWAnimationSpeedSetting,
     ) -> Result<(), BluetoothError>;
 }
 
@@ -60,7 +326,20 @@ impl<D: Device + std::marker::Sync> HWAnimate for D {
         protocol: &'e P,
         option: &'e HWAnimateOption,
     ) -> Result<(), BluetoothError> {
-        device.push(&protocol.hw_animate(option)[..]).await?;
+        if !protocol.hw_animations(option.speed()).contains(option) {
+            return Err(BluetoothError::Unsupported(option.to_string()));
+        }
+        for frame in protocol.hw_animate_frames(option) {
+            device
+                .push_command(protocol, &CommandKind::HWAnimate, &frame[..])
+                .await?;
+        }
+        device.state_cache().update(|state| {
+            state.mode = Some(LightMode::HWAnimation {
+                name: option.to_string(),
+                period: protocol.animation_period(option.speed()),
+            })
+        });
         Ok(())
     }
 
@@ -72,9 +351,50 @@ impl<D: Device + std::marker::Sync> HWAnimate for D {
         &self,
         protocol: &'e P,
         color: &'e HWStaticColorOption,
-        speed: &'e HWAnimationSpeedSetting
+        speed: &'e HWAnimationSpeedSetting,
     ) -> Result<(), BluetoothError> {
-        self.push(&protocol.hw_animate(&HWAnimateOption::Pulsating(color, speed))[..]).await?;
-        Ok(())
+        HWAnimate::set(self, protocol, &HWAnimateOption::Pulsating(color, speed)).await
+    }
+    async fn hw_anim_rainbow_pulsating<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError> {
+        HWAnimate::set(self, protocol, &HWAnimateOption::RainbowPulsating(speed)).await
+    }
+    async fn hw_anim_pulsating_bicolor<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        colors: &'e HWBicolorOption,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError> {
+        HWAnimate::set(
+            self,
+            protocol,
+            &HWAnimateOption::PulsatingBicolor(colors, speed),
+        )
+        .await
+    }
+    async fn hw_anim_rainbow_flashing<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError> {
+        HWAnimate::set(self, protocol, &HWAnimateOption::RainbowFlashing(speed)).await
+    }
+    async fn hw_anim_flashing<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        color: &'e HWStaticColorOption,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError> {
+        HWAnimate::set(self, protocol, &HWAnimateOption::Flashing(color, speed)).await
+    }
+    async fn hw_anim_rainbow_jumping<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        speed: &'e HWAnimationSpeedSetting,
+    ) -> Result<(), BluetoothError> {
+        HWAnimate::set(self, protocol, &HWAnimateOption::RainbowJumping(speed)).await
     }
 }
@@ -1,12 +1,14 @@
-use crate::communication_protocol::Protocol;
+use crate::communication_protocol::{CommandKind, Protocol};
 use crate::device::Device;
 use crate::device::Write;
-use crate::error::{BluetoothError};
+use crate::error::BluetoothError;
 use async_trait::async_trait;
 
 //-------//
 // Light //
 //-------//
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LightOption {
     On,
     Off,
@@ -19,7 +21,6 @@ pub trait Light {
         option: &'e LightOption,
     ) -> Result<(), BluetoothError>;
 
-
     // -------------------------------//
     // Syntactic sugar /////////////////
     // more idiomatic syntactic sugar //
@@ -45,11 +46,15 @@ impl<D: Device + std::marker::Sync> Light for D {
         protocol: &'e P,
         option: &'e LightOption,
     ) -> Result<(), BluetoothError> {
-        device.push(&protocol.light(option)[..]).await?;
+        device
+            .push_command(protocol, &CommandKind::Light, &protocol.light(option)[..])
+            .await?;
+        device
+            .state_cache()
+            .update(|state| state.power = Some(*option));
         Ok(())
     }
 
-
     // -------------------------------//
     // Syntactic sugar /////////////////
     // more idiomatic syntactic sugar //
@@ -57,15 +62,13 @@ impl<D: Device + std::marker::Sync> Light for D {
     async fn turn_on<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
         &self,
         protocol: &'e P,
-    ) -> Result<(), BluetoothError>{
-        self.push(&protocol.light(&LightOption::On)[..]).await?;
-        Ok(())
+    ) -> Result<(), BluetoothError> {
+        Light::set(self, protocol, &LightOption::On).await
     }
     async fn turn_off<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
         &self,
         protocol: &'e P,
-    ) -> Result<(), BluetoothError>{
-        self.push(&protocol.light(&LightOption::Off)[..]).await?;
-        Ok(())
+    ) -> Result<(), BluetoothError> {
+        Light::set(self, protocol, &LightOption::Off).await
     }
 }
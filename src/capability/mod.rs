@@ -6,5 +6,11 @@ pub mod color;
 pub mod hw_animate;
 #[cfg(feature = "light")]
 pub mod light;
+#[cfg(feature = "music")]
+pub mod music;
+#[cfg(feature = "pixels")]
+pub mod pixels;
 #[cfg(feature = "sw_animate")]
 pub mod sw_animate;
+#[cfg(feature = "temperature")]
+pub mod temperature;
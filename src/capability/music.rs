@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::communication_protocol::{CommandKind, Protocol};
+use crate::device::Write;
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use crate::units::Level;
+use async_trait::async_trait;
+
+//-------//
+// Music //
+//-------//
+/// Built-in microphone mode: the controller listens through its
+/// own microphone and animates the lights to the sound.
+///
+/// `sensitivity` goes from `0.0` (loud sounds only) to `1.0`
+/// (any sound); see `Protocol::music` for the protocols supporting it.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::music::{MusicOption, MusicPattern};
+/// use ble_ledly::units::Level;
+///
+/// let option = MusicOption::On(MusicPattern::Spectrum, Level::FULL);
+///
+/// assert_eq!(option.to_string(), "Music Spectrum");
+/// assert_eq!(MusicOption::Off.to_string(), "Music off");
+/// assert_eq!(MusicPattern::all().len(), 4);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MusicOption {
+    On(MusicPattern, Level),
+    Off,
+}
+
+/// Style of the microphone mode, as named by the vendor apps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MusicPattern {
+    /// Whole strip flashing on the beat
+    Energic,
+    /// Colors changing on the beat
+    Rhythm,
+    /// Color following the pitch
+    Spectrum,
+    /// Colors rolling with the volume
+    Rolling,
+}
+
+static PATTERNS: [MusicPattern; 4] = [
+    MusicPattern::Energic,
+    MusicPattern::Rhythm,
+    MusicPattern::Spectrum,
+    MusicPattern::Rolling,
+];
+
+impl MusicPattern {
+    pub fn all() -> &'static [MusicPattern] {
+        &PATTERNS
+    }
+}
+
+impl fmt::Display for MusicOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MusicOption::On(pattern, _) => write!(f, "Music {}", pattern),
+            MusicOption::Off => write!(f, "Music off"),
+        }
+    }
+}
+
+impl fmt::Display for MusicPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[async_trait]
+pub trait HWMusic {
+    async fn set<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        device: &Self,
+        protocol: &'e P,
+        option: &'e MusicOption,
+    ) -> Result<(), BluetoothError>;
+
+    // -------------------------------//
+    // Syntactic sugar /////////////////
+    // more idiomatic syntactic sugar //
+    // -------------------------------//
+    async fn music_on<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        pattern: MusicPattern,
+        sensitivity: Level,
+    ) -> Result<(), BluetoothError>;
+    async fn music_off<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+    ) -> Result<(), BluetoothError>;
+}
+
+//-------------------------//
+// Blanket implementations //
+//-------------------------//
+#[async_trait]
+impl<D: Device + std::marker::Sync> HWMusic for D {
+    // bound type to be transferred across threads
+    async fn set<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        device: &Self,
+        protocol: &'e P,
+        option: &'e MusicOption,
+    ) -> Result<(), BluetoothError> {
+        let frames = protocol
+            .music(option)
+            .ok_or_else(|| BluetoothError::Unsupported(option.to_string()))?;
+        for frame in frames {
+            device
+                .push_command(protocol, &CommandKind::Music, &frame[..])
+                .await?;
+        }
+        device.state_cache().update(|state| match option {
+            MusicOption::On(pattern, _) => state.mode = Some(LightMode::Music(pattern.to_string())),
+            // back to a mode the controller does not report
+            MusicOption::Off => state.mode = None,
+        });
+        Ok(())
+    }
+
+    // -------------------------------//
+    // Syntactic sugar /////////////////
+    // more idiomatic syntactic sugar //
+    // -------------------------------//
+    async fn music_on<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        pattern: MusicPattern,
+        sensitivity: Level,
+    ) -> Result<(), BluetoothError> {
+        HWMusic::set(self, protocol, &MusicOption::On(pattern, sensitivity)).await
+    }
+    async fn music_off<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+    ) -> Result<(), BluetoothError> {
+        HWMusic::set(self, protocol, &MusicOption::Off).await
+    }
+}
@@ -0,0 +1,126 @@
+use crate::capability::color::{device_color, ColorOption};
+use crate::communication_protocol::{CommandKind, Protocol};
+use crate::device::Write;
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use async_trait::async_trait;
+
+use std::ops::Range;
+
+//--------//
+// Pixels //
+//--------//
+/// Addressable pixel commands, pixels are zero-indexed
+pub enum PixelsOption<'e> {
+    /// Same color for every pixel in the range
+    Segment(Range<u16>, &'e ColorOption),
+    /// One color per pixel, starting from the first one
+    Frame(&'e [ColorOption]),
+}
+
+/// Protocols driving addressable (per-pixel) controllers
+pub trait PixelProtocol: Protocol {
+    /// Fails if the command does not fit the frame, e.g. too many
+    /// pixels, or if a segment is empty
+    fn pixels(&self, option: &PixelsOption) -> Result<Vec<u8>, BluetoothError>;
+}
+
+#[async_trait]
+pub trait Pixels {
+    async fn set<'e, P: PixelProtocol + std::marker::Send + std::marker::Sync>(
+        device: &Self,
+        protocol: &'e P,
+        option: &'e PixelsOption,
+    ) -> Result<(), BluetoothError>;
+
+    // -------------------------------//
+    // Syntactic sugar /////////////////
+    // more idiomatic syntactic sugar //
+    // -------------------------------//
+    async fn segment<'e, P: PixelProtocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        range: Range<u16>,
+        r: u8,
+        g: u8,
+        b: u8,
+    ) -> Result<(), BluetoothError>;
+    async fn frame<'e, P: PixelProtocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        frame: &'e [ColorOption],
+    ) -> Result<(), BluetoothError>;
+}
+
+//-------------------------//
+// Blanket implementations //
+//-------------------------//
+#[async_trait]
+impl<D: Device + std::marker::Sync> Pixels for D {
+    // bound type to be transferred across threads
+    async fn set<'e, P: PixelProtocol + std::marker::Send + std::marker::Sync>(
+        device: &Self,
+        protocol: &'e P,
+        option: &'e PixelsOption,
+    ) -> Result<(), BluetoothError> {
+        device
+            .push_command(
+                protocol,
+                &CommandKind::Pixels,
+                &calibrated_pixels(device, protocol, option)?[..],
+            )
+            .await?;
+        device
+            .state_cache()
+            .update(|state| state.mode = Some(LightMode::Pixels));
+        Ok(())
+    }
+
+    // -------------------------------//
+    // Syntactic sugar /////////////////
+    // more idiomatic syntactic sugar //
+    // -------------------------------//
+    async fn segment<'e, P: PixelProtocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        range: Range<u16>,
+        r: u8,
+        g: u8,
+        b: u8,
+    ) -> Result<(), BluetoothError> {
+        Pixels::set(
+            self,
+            protocol,
+            &PixelsOption::Segment(range, &ColorOption::RGB(r, g, b)),
+        )
+        .await
+    }
+    async fn frame<'e, P: PixelProtocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        frame: &'e [ColorOption],
+    ) -> Result<(), BluetoothError> {
+        Pixels::set(self, protocol, &PixelsOption::Frame(frame)).await
+    }
+}
+
+// Encodes a pixels command with the channel layout and calibration of the device
+fn calibrated_pixels<D: Device, P: PixelProtocol>(
+    device: &D,
+    protocol: &P,
+    option: &PixelsOption,
+) -> Result<Vec<u8>, BluetoothError> {
+    match option {
+        PixelsOption::Segment(range, color) => protocol.pixels(&PixelsOption::Segment(
+            range.clone(),
+            &device_color(device, protocol, color),
+        )),
+        PixelsOption::Frame(frame) => {
+            let frame: Vec<ColorOption> = frame
+                .iter()
+                .map(|color| device_color(device, protocol, color))
+                .collect();
+            protocol.pixels(&PixelsOption::Frame(&frame))
+        }
+    }
+}
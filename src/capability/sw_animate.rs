@@ -1,9 +1,11 @@
-use super::brightness::BrightnessOption;
-use crate::capability::color::ColorOption;
-use crate::communication_protocol::Protocol;
-use crate::device::Device;
+use super::brightness::{brightness_commands, BrightnessOption};
+use crate::capability::color::{device_color, ColorOption};
+use crate::communication_protocol::{CommandKind, Protocol};
 use crate::device::Write;
-use crate::error::{BluetoothError};
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use crate::palette::Palette;
+use crate::units::Level;
 use async_trait::async_trait;
 
 use std::time::Duration;
@@ -14,10 +16,12 @@ use tokio::time;
 //---------//
 pub enum SWAnimateOption<'e> {
     Breathing(&'e ColorOption, &'e SWAnimationRepeat, &'e SWAnimationSpeed),
+    /// Fades through the colors of the palette, back to the first one
+    CrossFade(&'e Palette, &'e SWAnimationRepeat, &'e SWAnimationSpeed),
 }
 
 pub enum SWAnimationRepeat {
-    FiniteCount(i32),
+    FiniteCount(u32),
     InfiniteCount,
 }
 
@@ -30,6 +34,9 @@ pub enum SWAnimationSpeed {
     Faster,
     Fastest,
 }
+// steps of the fade between two colors of a palette
+const FADE_STEPS: usize = 50;
+
 //--------------------//
 // Enum value mapping //
 //--------------------//
@@ -65,6 +72,19 @@ pub trait SWAnimate {
         repeat: &'e SWAnimationRepeat,
         speed: &'e SWAnimationSpeed,
     ) -> Result<(), BluetoothError>;
+    async fn _cross_fade<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        palette: &'e Palette,
+        interval: u64,
+    ) -> Result<(), BluetoothError>;
+    async fn cross_fade<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        palette: &'e Palette,
+        repeat: &'e SWAnimationRepeat,
+        speed: &'e SWAnimationSpeed,
+    ) -> Result<(), BluetoothError>;
 }
 
 //-------------------------//
@@ -82,6 +102,9 @@ impl<D: Device + std::marker::Sync> SWAnimate for D {
             SWAnimateOption::Breathing(color, repeat, speed) => {
                 self.breathing(protocol, color, repeat, speed).await?;
             }
+            SWAnimateOption::CrossFade(palette, repeat, speed) => {
+                self.cross_fade(protocol, palette, repeat, speed).await?;
+            }
         }
         Ok(())
     }
@@ -98,19 +121,21 @@ impl<D: Device + std::marker::Sync> SWAnimate for D {
     ) -> Result<(), BluetoothError> {
         // TODO: replace loops with sine impl.
         for i in 0..=100 {
-            let e_bytes = protocol.brightness(&BrightnessOption::LevelWithColor(
-                i as f32 / 100 as f32,
-                color,
-            ));
-            self.push(&(e_bytes)[..]).await?;
+            let level = Level::saturating(i as f32 / 100.0);
+            let option = BrightnessOption::LevelWithColor(level, color);
+            for (command, e_bytes) in brightness_commands(self, protocol, &option) {
+                self.push_command(protocol, &command, &(e_bytes)[..])
+                    .await?;
+            }
             time::sleep(Duration::from_millis(interval)).await;
         }
         for i in (0..=100).rev() {
-            let e_bytes = protocol.brightness(&BrightnessOption::LevelWithColor(
-                i as f32 / 100 as f32,
-                color,
-            ));
-            self.push(&(e_bytes)[..]).await?;
+            let level = Level::saturating(i as f32 / 100.0);
+            let option = BrightnessOption::LevelWithColor(level, color);
+            for (command, e_bytes) in brightness_commands(self, protocol, &option) {
+                self.push_command(protocol, &command, &(e_bytes)[..])
+                    .await?;
+            }
             time::sleep(Duration::from_millis(interval)).await;
         }
         Ok(())
@@ -123,17 +148,78 @@ impl<D: Device + std::marker::Sync> SWAnimate for D {
         repeat: &'e SWAnimationRepeat,
         speed: &'e SWAnimationSpeed,
     ) -> Result<(), BluetoothError> {
+        self.state_cache().update(|state| {
+            state.color = Some(*color);
+            state.mode = Some(LightMode::SWAnimation("Breathing".to_string()));
+        });
         match repeat {
             SWAnimationRepeat::FiniteCount(count) => {
                 let mut i = 0;
                 while i < *count {
-                    self._breathing(protocol, &color, sw_animation_speed(speed))
+                    self._breathing(protocol, color, sw_animation_speed(speed))
                         .await?;
                     i += 1;
                 }
+                // left at the lowest level of the last breath
+                self.state_cache()
+                    .update(|state| state.brightness = Some(Level::OFF));
+            }
+            SWAnimationRepeat::InfiniteCount => loop {
+                self._breathing(protocol, color, sw_animation_speed(speed))
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    async fn _cross_fade<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        palette: &'e Palette,
+        interval: u64,
+    ) -> Result<(), BluetoothError> {
+        let steps = palette.colors.len() * FADE_STEPS;
+        for step in 0..steps {
+            let color = palette.sample_cyclic(step as f32 / steps as f32);
+            let e_bytes = protocol.color(&device_color(self, protocol, &color));
+            self.push_command(protocol, &CommandKind::Color, &(e_bytes)[..])
+                .await?;
+            time::sleep(Duration::from_millis(interval)).await;
+        }
+        Ok(())
+    }
+
+    async fn cross_fade<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        palette: &'e Palette,
+        repeat: &'e SWAnimationRepeat,
+        speed: &'e SWAnimationSpeed,
+    ) -> Result<(), BluetoothError> {
+        self.state_cache().update(|state| {
+            state.mode = Some(LightMode::SWAnimation(format!(
+                "Cross-fade {}",
+                palette.name
+            )));
+        });
+        match repeat {
+            SWAnimationRepeat::FiniteCount(count) => {
+                for _ in 0..*count {
+                    self._cross_fade(protocol, palette, sw_animation_speed(speed))
+                        .await?;
+                }
+                // every cross-fade ends where it started
+                if let Some(first) = palette.colors.first() {
+                    self.state_cache().update(|state| {
+                        state.color = Some(*first);
+                        if !protocol.native_brightness() {
+                            state.brightness = Some(Level::FULL);
+                        }
+                    });
+                }
             }
             SWAnimationRepeat::InfiniteCount => loop {
-                self._breathing(protocol, &color, sw_animation_speed(speed))
+                self._cross_fade(protocol, palette, sw_animation_speed(speed))
                     .await?;
             },
         }
@@ -0,0 +1,193 @@
+use crate::capability::color::{device_color, ColorOption};
+use crate::communication_protocol::{CommandKind, Protocol};
+use crate::device::Write;
+use crate::device::{Device, LightMode};
+use crate::error::BluetoothError;
+use crate::units::{Kelvin, Level};
+use async_trait::async_trait;
+
+//-------------//
+// Temperature //
+//-------------//
+/// White light at a color temperature, optionally with a brightness level.
+///
+/// Protocols with a native CCT command encode it with
+/// `Protocol::white_temperature`; on the others it is sent as the
+/// blackbody color of the temperature, see `TemperatureOption::color`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TemperatureOption {
+    Kelvin(Kelvin),
+    KelvinWithBrightness(Kelvin, Level),
+}
+
+impl TemperatureOption {
+    pub fn kelvin(&self) -> Kelvin {
+        match self {
+            TemperatureOption::Kelvin(kelvin)
+            | TemperatureOption::KelvinWithBrightness(kelvin, _) => *kelvin,
+        }
+    }
+
+    /// Approximated blackbody color, scaled by the brightness if any.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::capability::temperature::TemperatureOption;
+    /// use ble_ledly::units::{Kelvin, Level};
+    ///
+    /// let daylight = Kelvin::new(6600).unwrap();
+    /// assert_eq!(TemperatureOption::Kelvin(daylight).color(), ColorOption::RGB(255, 255, 255));
+    /// let (warm, half) = (Kelvin::new(2700).unwrap(), Level::new(0.5).unwrap());
+    /// assert_eq!(
+    ///     TemperatureOption::KelvinWithBrightness(warm, half).color(),
+    ///     ColorOption::RGB(127, 83, 43)
+    /// );
+    /// ```
+    pub fn color(&self) -> ColorOption {
+        let color = ColorOption::from(self.kelvin());
+        match self {
+            TemperatureOption::Kelvin(_) => color,
+            TemperatureOption::KelvinWithBrightness(_, level) => color.scaled(*level),
+        }
+    }
+
+    /// Levels of the warm and cold white LEDs of a controller, from
+    /// `0.0` to `1.0`, mixing the temperature between their own
+    /// (`warm` and `cold`) in mired; scaled by the brightness if any.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::temperature::TemperatureOption;
+    /// use ble_ledly::units::{Kelvin, Level};
+    ///
+    /// let (warm, cold) = (Kelvin::new(2700).unwrap(), Kelvin::new(6500).unwrap());
+    ///
+    /// assert_eq!(TemperatureOption::Kelvin(warm).white_channels(warm, cold), (Level::FULL, Level::OFF));
+    /// assert_eq!(TemperatureOption::Kelvin(Kelvin::MAX).white_channels(warm, cold), (Level::OFF, Level::FULL));
+    /// assert_eq!(
+    ///     TemperatureOption::KelvinWithBrightness(warm, Level::new(0.5).unwrap())
+    ///         .white_channels(warm, cold),
+    ///     (Level::new(0.5).unwrap(), Level::OFF)
+    /// );
+    /// ```
+    pub fn white_channels(&self, warm: Kelvin, cold: Kelvin) -> (Level, Level) {
+        let mired = |kelvin: Kelvin| 1_000_000.0 / kelvin.get() as f32;
+        let span = mired(warm) - mired(cold);
+        let coldness = if span > 0.0 {
+            ((mired(warm) - mired(self.kelvin())) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let level = match self {
+            TemperatureOption::Kelvin(_) => 1.0,
+            TemperatureOption::KelvinWithBrightness(_, level) => level.get(),
+        };
+        (
+            Level::saturating((1.0 - coldness) * level),
+            Level::saturating(coldness * level),
+        )
+    }
+}
+
+#[async_trait]
+pub trait Temperature {
+    async fn set<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        device: &Self,
+        protocol: &'e P,
+        option: &'e TemperatureOption,
+    ) -> Result<(), BluetoothError>;
+
+    // -------------------------------//
+    // Syntactic sugar /////////////////
+    // more idiomatic syntactic sugar //
+    // -------------------------------//
+    async fn set_temperature<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        kelvin: Kelvin,
+    ) -> Result<(), BluetoothError>;
+    async fn set_temperature_with_brightness<
+        'e,
+        P: Protocol + std::marker::Send + std::marker::Sync,
+    >(
+        &self,
+        protocol: &'e P,
+        kelvin: Kelvin,
+        level: Level,
+    ) -> Result<(), BluetoothError>;
+}
+
+//-------------------------//
+// Blanket implementations //
+//-------------------------//
+#[async_trait]
+impl<D: Device + std::marker::Sync> Temperature for D {
+    // bound type to be transferred across threads
+    async fn set<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        device: &Self,
+        protocol: &'e P,
+        option: &'e TemperatureOption,
+    ) -> Result<(), BluetoothError> {
+        match protocol.white_temperature(option) {
+            Some(frame) => {
+                device
+                    .push_command(protocol, &CommandKind::Temperature, &frame[..])
+                    .await?
+            }
+            // no native CCT command, fall back to the blackbody color
+            None => {
+                device
+                    .push_command(
+                        protocol,
+                        &CommandKind::Color,
+                        &protocol.color(&device_color(device, protocol, &option.color()))[..],
+                    )
+                    .await?
+            }
+        }
+        // the blackbody color stands for the white, at the given level
+        device.state_cache().update(|state| {
+            state.color = Some(ColorOption::from(option.kelvin()));
+            match option {
+                TemperatureOption::KelvinWithBrightness(_, level) => {
+                    state.brightness = Some(*level)
+                }
+                TemperatureOption::Kelvin(_) if !protocol.native_brightness() => {
+                    state.brightness = Some(Level::FULL)
+                }
+                TemperatureOption::Kelvin(_) => {}
+            }
+            state.mode = Some(LightMode::Temperature(option.kelvin()));
+        });
+        Ok(())
+    }
+
+    // -------------------------------//
+    // Syntactic sugar /////////////////
+    // more idiomatic syntactic sugar //
+    // -------------------------------//
+    async fn set_temperature<'e, P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &'e P,
+        kelvin: Kelvin,
+    ) -> Result<(), BluetoothError> {
+        Temperature::set(self, protocol, &TemperatureOption::Kelvin(kelvin)).await
+    }
+    async fn set_temperature_with_brightness<
+        'e,
+        P: Protocol + std::marker::Send + std::marker::Sync,
+    >(
+        &self,
+        protocol: &'e P,
+        kelvin: Kelvin,
+        level: Level,
+    ) -> Result<(), BluetoothError> {
+        Temperature::set(
+            self,
+            protocol,
+            &TemperatureOption::KelvinWithBrightness(kelvin, level),
+        )
+        .await
+    }
+}
@@ -0,0 +1,543 @@
+//! Color representations accepted wherever a `ColorOption` is: every type
+//! converts to and from `ColorOption` with `From`, and `ColorOption` parses
+//! from the usual string notations.
+//!
+//! | Notation                        | Example               |
+//! |---------------------------------|-----------------------|
+//! | Hex, 6 or 3 digits              | `#ff8800`, `#f80`     |
+//! | Hex with white, 8 or 10 digits  | `#ff880040`           |
+//! | RGB, `0..=255` or percentages   | `rgb(255, 136, 0)`    |
+//! | HSV, hue in degrees             | `hsv(30, 100%, 100%)` |
+//! | HSL, hue in degrees             | `hsl(30, 100%, 50%)`  |
+//! | CIE xy chromaticity             | `xy(0.55, 0.41)`      |
+//! | Color temperature               | `2700K`               |
+//! | CSS color name                  | `orange`, `teal`      |
+//!
+//! Saturation, value and lightness are percentages when suffixed with `%`
+//! or greater than `1`, fractions otherwise. Converting a `ColorOption` to
+//! HSV or HSL and back is lossless; `Xy` and `Kelvin` only carry the
+//! chromaticity, they convert back at full brightness.
+//!
+//! ## Examples
+//! ```
+//! use ble_ledly::capability::color::ColorOption;
+//! use ble_ledly::color::{Hsl, Hsv, Xy};
+//! use ble_ledly::error::ColorParseError;
+//!
+//! let orange: ColorOption = "#ff8800".parse().unwrap();
+//! assert_eq!(orange, ColorOption::RGB(255, 136, 0));
+//! assert_eq!("hsv(32, 100%, 100%)".parse::<ColorOption>().unwrap(), orange);
+//! assert_eq!("darkorange".parse::<ColorOption>().unwrap(), ColorOption::RGB(255, 140, 0));
+//! assert_eq!(orange.to_hex(), "#ff8800");
+//!
+//! let warm = ColorOption::RGBWW(255, 136, 0, 64, 0);
+//! assert_eq!(warm.to_hex(), "#ff88004000");
+//! assert_eq!("#ff88004000".parse::<ColorOption>().unwrap(), warm);
+//!
+//! let hsv = Hsv::from(orange);
+//! assert_eq!(hsv.hue.round(), 32.0);
+//! assert_eq!(ColorOption::from(hsv), orange);
+//! assert_eq!(ColorOption::from(Hsl::from(orange)), orange);
+//!
+//! let xy = Xy::from(ColorOption::RGB(255, 0, 0));
+//! assert_eq!(ColorOption::from(xy), ColorOption::RGB(255, 0, 0));
+//!
+//! assert_eq!(
+//!     "hsv(30, 120%, 100%)".parse::<ColorOption>(),
+//!     Err(ColorParseError::InvalidComponents("hsv(30, 120%, 100%)".to_string()))
+//! );
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use crate::capability::color::ColorOption;
+use crate::error::ColorParseError;
+pub use crate::units::Kelvin;
+
+/// Hue in degrees (`0.0..360.0`), saturation and value from `0.0` to `1.0`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsv {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+/// Hue in degrees (`0.0..360.0`), saturation and lightness from `0.0` to `1.0`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsl {
+    pub hue: f32,
+    pub saturation: f32,
+    pub lightness: f32,
+}
+
+/// CIE 1931 chromaticity, through the wide gamut D65
+/// conversion used by _Philips Hue_ bulbs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Xy {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Hsv {
+    pub fn new(hue: f32, saturation: f32, value: f32) -> Self {
+        Self {
+            hue,
+            saturation,
+            value,
+        }
+    }
+}
+
+impl Hsl {
+    pub fn new(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self {
+            hue,
+            saturation,
+            lightness,
+        }
+    }
+}
+
+impl Xy {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
+//-------------//
+// Conversions //
+//-------------//
+impl From<ColorOption> for Hsv {
+    fn from(color: ColorOption) -> Self {
+        let (r, g, b) = _unit(color);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        Hsv {
+            hue: _hue(r, g, b, max, delta),
+            saturation: if max == 0.0 { 0.0 } else { delta / max },
+            value: max,
+        }
+    }
+}
+
+impl From<Hsv> for ColorOption {
+    fn from(hsv: Hsv) -> Self {
+        let value = hsv.value.clamp(0.0, 1.0);
+        let chroma = value * hsv.saturation.clamp(0.0, 1.0);
+        _from_chroma(hsv.hue, chroma, value - chroma)
+    }
+}
+
+impl From<ColorOption> for Hsl {
+    fn from(color: ColorOption) -> Self {
+        let (r, g, b) = _unit(color);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let lightness = (max + min) / 2.0;
+        Hsl {
+            hue: _hue(r, g, b, max, delta),
+            saturation: if delta == 0.0 {
+                0.0
+            } else {
+                delta / (1.0 - (2.0 * lightness - 1.0).abs())
+            },
+            lightness,
+        }
+    }
+}
+
+impl From<Hsl> for ColorOption {
+    fn from(hsl: Hsl) -> Self {
+        let lightness = hsl.lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * hsl.saturation.clamp(0.0, 1.0);
+        _from_chroma(hsl.hue, chroma, lightness - chroma / 2.0)
+    }
+}
+
+impl From<ColorOption> for Xy {
+    fn from(color: ColorOption) -> Self {
+        let linear = |c: f32| {
+            if c > 0.04045 {
+                ((c + 0.055) / 1.055).powf(2.4)
+            } else {
+                c / 12.92
+            }
+        };
+        let (r, g, b) = _unit(color);
+        let (r, g, b) = (linear(r), linear(g), linear(b));
+
+        let x = r * 0.664_511 + g * 0.154_324 + b * 0.162_028;
+        let y = r * 0.283_881 + g * 0.668_433 + b * 0.047_685;
+        let z = r * 0.000_088 + g * 0.072_310 + b * 0.986_039;
+
+        let sum = x + y + z;
+        if sum == 0.0 {
+            // black has no chromaticity, fallback to the white point
+            return Xy::new(0.3127, 0.3290);
+        }
+        Xy::new(x / sum, y / sum)
+    }
+}
+
+impl From<Xy> for ColorOption {
+    fn from(xy: Xy) -> Self {
+        if xy.y <= 0.0 {
+            return ColorOption::RGB(0, 0, 0);
+        }
+        let x = xy.x / xy.y;
+        let z = (1.0 - xy.x - xy.y) / xy.y;
+
+        let r = x * 1.656_492 - 0.354_851 - z * 0.255_038;
+        let g = -x * 0.707_196 + 1.655_397 + z * 0.036_152;
+        let b = x * 0.051_713 - 0.121_364 + z * 1.011_53;
+
+        // full brightness: the largest channel is saturated
+        let (r, g, b) = (r.max(0.0), g.max(0.0), b.max(0.0));
+        let max = r.max(g).max(b);
+        if max == 0.0 {
+            return ColorOption::RGB(0, 0, 0);
+        }
+        let gamma = |c: f32| {
+            let c = c / max;
+            let c = if c <= 0.003_130_8 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (c.clamp(0.0, 1.0) * 255.0).round() as u8
+        };
+        ColorOption::RGB(gamma(r), gamma(g), gamma(b))
+    }
+}
+
+impl From<Kelvin> for ColorOption {
+    fn from(kelvin: Kelvin) -> Self {
+        ColorOption::from_kelvin(kelvin.get())
+    }
+}
+
+impl From<(u8, u8, u8)> for ColorOption {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        ColorOption::RGB(r, g, b)
+    }
+}
+
+// channels from 0.0 to 1.0, white channels blended in
+fn _unit(color: ColorOption) -> (f32, f32, f32) {
+    let (r, g, b) = color.rgb();
+    (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+}
+
+fn _hue(r: f32, g: f32, b: f32, max: f32, delta: f32) -> f32 {
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    hue % 360.0
+}
+
+// shared by HSV and HSL: chroma, hue sector and the offset
+// added to every channel
+fn _from_chroma(hue: f32, chroma: f32, offset: f32) -> ColorOption {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let channel = |c: f32| ((c + offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+    ColorOption::RGB(channel(r), channel(g), channel(b))
+}
+
+//---------//
+// Parsing //
+//---------//
+impl ColorOption {
+    /// Color as a `#rrggbb` hex string, followed
+    /// by the white channels (`#rrggbbww`, `#rrggbbwwcc`)
+    pub fn to_hex(&self) -> String {
+        let channels = match *self {
+            ColorOption::RGB(r, g, b) => vec![r, g, b],
+            ColorOption::RGBW(r, g, b, w) => vec![r, g, b, w],
+            ColorOption::RGBWW(r, g, b, warm, cold) => vec![r, g, b, warm, cold],
+        };
+        channels.iter().fold(String::from("#"), |hex, channel| {
+            hex + &format!("{:02x}", channel)
+        })
+    }
+}
+
+impl fmt::Display for ColorOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for ColorOption {
+    type Err = ColorParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let color = source.trim().to_ascii_lowercase();
+        if let Some(hex) = color.strip_prefix('#') {
+            return _parse_hex(hex).ok_or_else(|| ColorParseError::InvalidHex(source.to_string()));
+        }
+        if let Some((function, arguments)) = color
+            .strip_suffix(')')
+            .and_then(|color| color.split_once('('))
+        {
+            let invalid = || ColorParseError::InvalidComponents(source.to_string());
+            let arguments: Vec<&str> = arguments
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|argument| !argument.is_empty())
+                .collect();
+            return match (function.trim(), &arguments[..]) {
+                ("rgb", [r, g, b]) => Ok(ColorOption::RGB(
+                    _parse_channel(r).ok_or_else(invalid)?,
+                    _parse_channel(g).ok_or_else(invalid)?,
+                    _parse_channel(b).ok_or_else(invalid)?,
+                )),
+                ("hsv", [h, s, v]) => Ok(ColorOption::from(Hsv::new(
+                    _parse_hue(h).ok_or_else(invalid)?,
+                    _parse_fraction(s).ok_or_else(invalid)?,
+                    _parse_fraction(v).ok_or_else(invalid)?,
+                ))),
+                ("hsl", [h, s, l]) => Ok(ColorOption::from(Hsl::new(
+                    _parse_hue(h).ok_or_else(invalid)?,
+                    _parse_fraction(s).ok_or_else(invalid)?,
+                    _parse_fraction(l).ok_or_else(invalid)?,
+                ))),
+                ("xy", [x, y]) => Ok(ColorOption::from(Xy::new(
+                    x.parse().map_err(|_| invalid())?,
+                    y.parse().map_err(|_| invalid())?,
+                ))),
+                _ => Err(invalid()),
+            };
+        }
+        if let Some(kelvin) = color.strip_suffix('k') {
+            if let Ok(kelvin) = kelvin.trim().parse::<u16>() {
+                return Kelvin::new(kelvin)
+                    .map(ColorOption::from)
+                    .map_err(|_| ColorParseError::InvalidComponents(source.to_string()));
+            }
+        }
+        CSS_COLORS
+            .binary_search_by(|(name, _)| name.cmp(&color.as_str()))
+            .map(|index| ColorOption::from(CSS_COLORS[index].1))
+            .map_err(|_| ColorParseError::UnknownName(source.to_string()))
+    }
+}
+
+fn _parse_hex(hex: &str) -> Option<ColorOption> {
+    let digits = hex
+        .chars()
+        .map(|digit| digit.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    if let [r, g, b] = digits[..] {
+        return Some(ColorOption::RGB(r * 0x11, g * 0x11, b * 0x11));
+    }
+    let channels: Vec<u8> = digits
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => Some(high << 4 | low),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    match channels[..] {
+        [r, g, b] => Some(ColorOption::RGB(r, g, b)),
+        [r, g, b, w] => Some(ColorOption::RGBW(r, g, b, w)),
+        [r, g, b, warm, cold] => Some(ColorOption::RGBWW(r, g, b, warm, cold)),
+        _ => None,
+    }
+}
+
+fn _parse_channel(channel: &str) -> Option<u8> {
+    match channel.strip_suffix('%') {
+        Some(percent) => {
+            let percent: f32 = percent.parse().ok()?;
+            (0.0..=100.0)
+                .contains(&percent)
+                .then(|| (percent * 2.55).round() as u8)
+        }
+        None => channel.parse().ok(),
+    }
+}
+
+fn _parse_hue(hue: &str) -> Option<f32> {
+    hue.strip_suffix("deg").unwrap_or(hue).parse().ok()
+}
+
+fn _parse_fraction(fraction: &str) -> Option<f32> {
+    let value: f32 = fraction
+        .strip_suffix('%')
+        .unwrap_or(fraction)
+        .parse()
+        .ok()?;
+    let value = if fraction.ends_with('%') || value > 1.0 {
+        value / 100.0
+    } else {
+        value
+    };
+    (0.0..=1.0).contains(&value).then_some(value)
+}
+
+// CSS named colors, sorted by name for the binary search
+pub(crate) static CSS_COLORS: [(&str, (u8, u8, u8)); 148] = [
+    ("aliceblue", (240, 248, 255)),
+    ("antiquewhite", (250, 235, 215)),
+    ("aqua", (0, 255, 255)),
+    ("aquamarine", (127, 255, 212)),
+    ("azure", (240, 255, 255)),
+    ("beige", (245, 245, 220)),
+    ("bisque", (255, 228, 196)),
+    ("black", (0, 0, 0)),
+    ("blanchedalmond", (255, 235, 205)),
+    ("blue", (0, 0, 255)),
+    ("blueviolet", (138, 43, 226)),
+    ("brown", (165, 42, 42)),
+    ("burlywood", (222, 184, 135)),
+    ("cadetblue", (95, 158, 160)),
+    ("chartreuse", (127, 255, 0)),
+    ("chocolate", (210, 105, 30)),
+    ("coral", (255, 127, 80)),
+    ("cornflowerblue", (100, 149, 237)),
+    ("cornsilk", (255, 248, 220)),
+    ("crimson", (220, 20, 60)),
+    ("cyan", (0, 255, 255)),
+    ("darkblue", (0, 0, 139)),
+    ("darkcyan", (0, 139, 139)),
+    ("darkgoldenrod", (184, 134, 11)),
+    ("darkgray", (169, 169, 169)),
+    ("darkgreen", (0, 100, 0)),
+    ("darkgrey", (169, 169, 169)),
+    ("darkkhaki", (189, 183, 107)),
+    ("darkmagenta", (139, 0, 139)),
+    ("darkolivegreen", (85, 107, 47)),
+    ("darkorange", (255, 140, 0)),
+    ("darkorchid", (153, 50, 204)),
+    ("darkred", (139, 0, 0)),
+    ("darksalmon", (233, 150, 122)),
+    ("darkseagreen", (143, 188, 143)),
+    ("darkslateblue", (72, 61, 139)),
+    ("darkslategray", (47, 79, 79)),
+    ("darkslategrey", (47, 79, 79)),
+    ("darkturquoise", (0, 206, 209)),
+    ("darkviolet", (148, 0, 211)),
+    ("deeppink", (255, 20, 147)),
+    ("deepskyblue", (0, 191, 255)),
+    ("dimgray", (105, 105, 105)),
+    ("dimgrey", (105, 105, 105)),
+    ("dodgerblue", (30, 144, 255)),
+    ("firebrick", (178, 34, 34)),
+    ("floralwhite", (255, 250, 240)),
+    ("forestgreen", (34, 139, 34)),
+    ("fuchsia", (255, 0, 255)),
+    ("gainsboro", (220, 220, 220)),
+    ("ghostwhite", (248, 248, 255)),
+    ("gold", (255, 215, 0)),
+    ("goldenrod", (218, 165, 32)),
+    ("gray", (128, 128, 128)),
+    ("green", (0, 128, 0)),
+    ("greenyellow", (173, 255, 47)),
+    ("grey", (128, 128, 128)),
+    ("honeydew", (240, 255, 240)),
+    ("hotpink", (255, 105, 180)),
+    ("indianred", (205, 92, 92)),
+    ("indigo", (75, 0, 130)),
+    ("ivory", (255, 255, 240)),
+    ("khaki", (240, 230, 140)),
+    ("lavender", (230, 230, 250)),
+    ("lavenderblush", (255, 240, 245)),
+    ("lawngreen", (124, 252, 0)),
+    ("lemonchiffon", (255, 250, 205)),
+    ("lightblue", (173, 216, 230)),
+    ("lightcoral", (240, 128, 128)),
+    ("lightcyan", (224, 255, 255)),
+    ("lightgoldenrodyellow", (250, 250, 210)),
+    ("lightgray", (211, 211, 211)),
+    ("lightgreen", (144, 238, 144)),
+    ("lightgrey", (211, 211, 211)),
+    ("lightpink", (255, 182, 193)),
+    ("lightsalmon", (255, 160, 122)),
+    ("lightseagreen", (32, 178, 170)),
+    ("lightskyblue", (135, 206, 250)),
+    ("lightslategray", (119, 136, 153)),
+    ("lightslategrey", (119, 136, 153)),
+    ("lightsteelblue", (176, 196, 222)),
+    ("lightyellow", (255, 255, 224)),
+    ("lime", (0, 255, 0)),
+    ("limegreen", (50, 205, 50)),
+    ("linen", (250, 240, 230)),
+    ("magenta", (255, 0, 255)),
+    ("maroon", (128, 0, 0)),
+    ("mediumaquamarine", (102, 205, 170)),
+    ("mediumblue", (0, 0, 205)),
+    ("mediumorchid", (186, 85, 211)),
+    ("mediumpurple", (147, 112, 219)),
+    ("mediumseagreen", (60, 179, 113)),
+    ("mediumslateblue", (123, 104, 238)),
+    ("mediumspringgreen", (0, 250, 154)),
+    ("mediumturquoise", (72, 209, 204)),
+    ("mediumvioletred", (199, 21, 133)),
+    ("midnightblue", (25, 25, 112)),
+    ("mintcream", (245, 255, 250)),
+    ("mistyrose", (255, 228, 225)),
+    ("moccasin", (255, 228, 181)),
+    ("navajowhite", (255, 222, 173)),
+    ("navy", (0, 0, 128)),
+    ("oldlace", (253, 245, 230)),
+    ("olive", (128, 128, 0)),
+    ("olivedrab", (107, 142, 35)),
+    ("orange", (255, 165, 0)),
+    ("orangered", (255, 69, 0)),
+    ("orchid", (218, 112, 214)),
+    ("palegoldenrod", (238, 232, 170)),
+    ("palegreen", (152, 251, 152)),
+    ("paleturquoise", (175, 238, 238)),
+    ("palevioletred", (219, 112, 147)),
+    ("papayawhip", (255, 239, 213)),
+    ("peachpuff", (255, 218, 185)),
+    ("peru", (205, 133, 63)),
+    ("pink", (255, 192, 203)),
+    ("plum", (221, 160, 221)),
+    ("powderblue", (176, 224, 230)),
+    ("purple", (128, 0, 128)),
+    ("rebeccapurple", (102, 51, 153)),
+    ("red", (255, 0, 0)),
+    ("rosybrown", (188, 143, 143)),
+    ("royalblue", (65, 105, 225)),
+    ("saddlebrown", (139, 69, 19)),
+    ("salmon", (250, 128, 114)),
+    ("sandybrown", (244, 164, 96)),
+    ("seagreen", (46, 139, 87)),
+    ("seashell", (255, 245, 238)),
+    ("sienna", (160, 82, 45)),
+    ("silver", (192, 192, 192)),
+    ("skyblue", (135, 206, 235)),
+    ("slateblue", (106, 90, 205)),
+    ("slategray", (112, 128, 144)),
+    ("slategrey", (112, 128, 144)),
+    ("snow", (255, 250, 250)),
+    ("springgreen", (0, 255, 127)),
+    ("steelblue", (70, 130, 180)),
+    ("tan", (210, 180, 140)),
+    ("teal", (0, 128, 128)),
+    ("thistle", (216, 191, 216)),
+    ("tomato", (255, 99, 71)),
+    ("turquoise", (64, 224, 208)),
+    ("violet", (238, 130, 238)),
+    ("wheat", (245, 222, 179)),
+    ("white", (255, 255, 255)),
+    ("whitesmoke", (245, 245, 245)),
+    ("yellow", (255, 255, 0)),
+    ("yellowgreen", (154, 205, 50)),
+];
@@ -0,0 +1,571 @@
+//! Data-driven protocol definitions.
+//!
+//! Most cheap light controllers speak a protocol made of fixed byte templates
+//! with a few variable fields. A `DeclarativeProtocol` loads those templates
+//! (plus the hardware animation tables) from a _TOML_ or _JSON_ file at runtime,
+//! so a new controller can be supported without recompiling.
+//!
+//! Templates are whitespace separated hex bytes and placeholders:
+//!
+//! | Placeholder  | Value                                       | Allowed in                |
+//! |--------------|---------------------------------------------|---------------------------|
+//! | `{r}`        | Red channel                                 | `color.rgb`               |
+//! | `{g}`        | Green channel                               | `color.rgb`               |
+//! | `{b}`        | Blue channel                                | `color.rgb`               |
+//! | `{level}`    | Brightness scaled to `0..=brightness.max`   | `brightness.level`        |
+//! | `{color}`    | Byte from `hw_animate.colors`               | `hw_animate.pulsating`    |
+//! | `{speed}`    | Byte from `hw_animate.speeds`               | `hw_animate.pulsating`    |
+//! | `{checksum}` | Checksum of the preceding bytes             | any, requires `checksum`  |
+//!
+//! `hw_animate.colors` maps `red`, `green` and `blue`, and optionally `yellow`,
+//! `cyan`, `purple` and `white`; only the pulsating animation is declarable.
+//! `hw_animate.speeds` lists the nine speed steps, slowest first; the optional
+//! `hw_animate.periods` gives their approximate period in milliseconds, which
+//! enables `HWAnimationSpeedSetting::Period` and `Protocol::animation_period`.
+//!
+//! ## Examples
+//!
+//! The built-in `GenericRGB` protocol expressed as a definition:
+//!
+//! ```
+//! use std::time::Duration;
+//! use ble_ledly::capability::color::ColorOption;
+//! use ble_ledly::capability::hw_animate::{
+//!     HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption,
+//! };
+//! use ble_ledly::capability::light::LightOption;
+//! use ble_ledly::communication_protocol::{DeclarativeProtocol, GenericRGB, Protocol};
+//!
+//! let definition = r#"
+//!     name = "Triones"
+//!
+//!     [light]
+//!     on = "cc 23 33"
+//!     off = "cc 24 33"
+//!
+//!     [color]
+//!     rgb = "56 {r} {g} {b} 00 f0 aa"
+//!
+//!     [hw_animate]
+//!     pulsating = "bb {color} {speed} 44"
+//!     colors = { red = 0x26, green = 0x27, blue = 0x28 }
+//!     speeds = [0x1f, 0x1b, 0x1a, 0x17, 0x13, 0x10, 0x0c, 0x05, 0x01]
+//!     periods = [6200, 5400, 5200, 4600, 3800, 3200, 2400, 1000, 200]
+//! "#;
+//!
+//! let protocol = DeclarativeProtocol::from_toml_str(definition).unwrap();
+//! let color = ColorOption::RGB(255, 128, 0);
+//!
+//! assert_eq!(protocol.name(), "Triones");
+//! assert_eq!(protocol.light(&LightOption::On), GenericRGB::default().light(&LightOption::On));
+//! assert_eq!(protocol.color(&color), GenericRGB::default().color(&color));
+//!
+//! let speed = HWAnimationSpeedSetting::Period(Duration::from_millis(900));
+//! let pulsating = HWAnimateOption::Pulsating(&HWStaticColorOption::Red, &speed);
+//! assert_eq!(protocol.hw_animate(&pulsating), vec![0xbb, 0x26, 0x05, 0x44]);
+//! assert_eq!(protocol.animation_period(&speed), Some(Duration::from_millis(1000)));
+//! ```
+//!
+//! A checksum is computed over the bytes preceding its placeholder, starting
+//! from the optional `start` offset:
+//!
+//! ```
+//! use ble_ledly::capability::brightness::BrightnessOption;
+//! use ble_ledly::capability::hw_animate::HWAnimationSpeedSetting;
+//! use ble_ledly::capability::light::LightOption;
+//! use ble_ledly::communication_protocol::{DeclarativeProtocol, Protocol};
+//! use ble_ledly::units::Level;
+//!
+//! let definition = r#"{
+//!     "name": "Zengge",
+//!     "checksum": { "algorithm": "sum8" },
+//!     "light": { "on": "71 23 0f {checksum}", "off": "71 24 0f {checksum}" },
+//!     "color": { "rgb": "31 {r} {g} {b} 00 00 0f {checksum}" }
+//! }"#;
+//!
+//! let protocol = DeclarativeProtocol::from_json_str(definition).unwrap();
+//! assert_eq!(protocol.light(&LightOption::On), vec![0x71, 0x23, 0x0f, 0xa3]);
+//!
+//! // no `brightness` table, a level alone is encoded as white
+//! assert_eq!(
+//!     protocol.brightness(&BrightnessOption::Level(Level::FULL)),
+//!     vec![0x31, 0xff, 0xff, 0xff, 0x00, 0x00, 0x0f, 0x3d]
+//! );
+//!
+//! // no `hw_animate` table, the `HWAnimate` capability reports them as unsupported
+//! assert!(protocol.hw_animations(&HWAnimationSpeedSetting::Speed5).is_empty());
+//! ```
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::ColorOption,
+        hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
+        light::LightOption,
+    },
+    communication_protocol::{framing::Checksum, GenericRGB, Protocol},
+    error::ProtocolDefinitionError,
+};
+
+// one entry per `HWAnimationSpeedSetting` variant
+const SPEED_STEPS: usize = 9;
+
+//------------//
+// Definition //
+//------------//
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Definition {
+    name: String,
+    checksum: Option<ChecksumDefinition>,
+    light: LightDefinition,
+    color: ColorDefinition,
+    brightness: Option<BrightnessDefinition>,
+    hw_animate: Option<HWAnimateDefinition>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+struct ChecksumDefinition {
+    algorithm: ChecksumAlgorithm,
+    #[serde(default)]
+    start: usize,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ChecksumAlgorithm {
+    Xor,
+    Sum8,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDefinition {
+    on: String,
+    off: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColorDefinition {
+    rgb: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BrightnessDefinition {
+    level: String,
+    #[serde(default = "default_level_max")]
+    max: u8,
+}
+
+fn default_level_max() -> u8 {
+    0xFF
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HWAnimateDefinition {
+    pulsating: String,
+    colors: StaticColorTable,
+    speeds: Vec<u8>,
+    // approximate period of each speed, in milliseconds
+    #[serde(default)]
+    periods: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StaticColorTable {
+    red: u8,
+    green: u8,
+    blue: u8,
+    #[serde(default)]
+    yellow: Option<u8>,
+    #[serde(default)]
+    cyan: Option<u8>,
+    #[serde(default)]
+    purple: Option<u8>,
+    #[serde(default)]
+    white: Option<u8>,
+}
+
+impl StaticColorTable {
+    fn get(&self, color: &HWStaticColorOption) -> Option<u8> {
+        match color {
+            HWStaticColorOption::Red => Some(self.red),
+            HWStaticColorOption::Green => Some(self.green),
+            HWStaticColorOption::Blue => Some(self.blue),
+            HWStaticColorOption::Yellow => self.yellow,
+            HWStaticColorOption::Cyan => self.cyan,
+            HWStaticColorOption::Purple => self.purple,
+            HWStaticColorOption::White => self.white,
+        }
+    }
+}
+
+//----------//
+// Template //
+//----------//
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Byte(u8),
+    Red,
+    Green,
+    Blue,
+    Level,
+    Color,
+    Speed,
+    Checksum,
+}
+
+impl Token {
+    fn placeholder(name: &str) -> Option<Token> {
+        match name {
+            "r" => Some(Token::Red),
+            "g" => Some(Token::Green),
+            "b" => Some(Token::Blue),
+            "level" => Some(Token::Level),
+            "color" => Some(Token::Color),
+            "speed" => Some(Token::Speed),
+            "checksum" => Some(Token::Checksum),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Values {
+    r: u8,
+    g: u8,
+    b: u8,
+    level: u8,
+    color: u8,
+    speed: u8,
+}
+
+struct Template {
+    tokens: Vec<Token>,
+    checksum: Option<ChecksumDefinition>,
+}
+
+impl Template {
+    fn parse(
+        command: &str,
+        source: &str,
+        allowed: &[Token],
+        checksum: Option<ChecksumDefinition>,
+    ) -> Result<Self, ProtocolDefinitionError> {
+        let mut tokens = Vec::new();
+
+        for raw in source.split_whitespace() {
+            let token = match raw.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+                Some(name) => {
+                    let token =
+                        Token::placeholder(name).ok_or(ProtocolDefinitionError::InvalidToken {
+                            command: command.to_string(),
+                            token: raw.to_string(),
+                        })?;
+                    if token != Token::Checksum && !allowed.contains(&token) {
+                        return Err(ProtocolDefinitionError::UnexpectedPlaceholder {
+                            command: command.to_string(),
+                            placeholder: raw.to_string(),
+                        });
+                    }
+                    token
+                }
+                None => Token::Byte(
+                    u8::from_str_radix(raw.trim_start_matches("0x"), 16).map_err(|_| {
+                        ProtocolDefinitionError::InvalidToken {
+                            command: command.to_string(),
+                            token: raw.to_string(),
+                        }
+                    })?,
+                ),
+            };
+            tokens.push(token);
+        }
+
+        // validate checksum usage once, so rendering never fails
+        if let Some(position) = tokens.iter().position(|t| *t == Token::Checksum) {
+            let definition = checksum.ok_or(ProtocolDefinitionError::MissingChecksum {
+                command: command.to_string(),
+            })?;
+            if definition.start > position {
+                return Err(ProtocolDefinitionError::InvalidChecksumRange {
+                    command: command.to_string(),
+                });
+            }
+        }
+
+        Ok(Self { tokens, checksum })
+    }
+
+    fn render(&self, values: &Values) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.tokens.len());
+        for token in self.tokens.iter() {
+            let byte = match token {
+                Token::Byte(byte) => *byte,
+                Token::Red => values.r,
+                Token::Green => values.g,
+                Token::Blue => values.b,
+                Token::Level => values.level,
+                Token::Color => values.color,
+                Token::Speed => values.speed,
+                Token::Checksum => {
+                    // presence checked in `Template::parse`
+                    let checksum = self.checksum.expect("validated checksum definition");
+                    let covered = &bytes[checksum.start..];
+                    match checksum.algorithm {
+                        ChecksumAlgorithm::Xor => Checksum::Xor.compute(covered)[0],
+                        ChecksumAlgorithm::Sum8 => Checksum::Sum8.compute(covered)[0],
+                    }
+                }
+            };
+            bytes.push(byte);
+        }
+        bytes
+    }
+}
+
+//---------------------//
+// DeclarativeProtocol //
+//---------------------//
+struct BrightnessTable {
+    level: Template,
+    max: u8,
+}
+
+struct HWAnimateTable {
+    pulsating: Template,
+    colors: StaticColorTable,
+    speeds: Vec<u8>,
+    periods: Vec<Duration>,
+}
+
+impl HWAnimateTable {
+    // entry of the speed tables, `None` for a period
+    // when the definition does not declare them
+    fn speed_index(&self, setting: &HWAnimationSpeedSetting) -> Option<usize> {
+        Some(match setting {
+            HWAnimationSpeedSetting::Speed1 => 0,
+            HWAnimationSpeedSetting::Speed2 => 1,
+            HWAnimationSpeedSetting::Speed3 => 2,
+            HWAnimationSpeedSetting::Speed4 => 3,
+            HWAnimationSpeedSetting::Speed5 => 4,
+            HWAnimationSpeedSetting::Speed6 => 5,
+            HWAnimationSpeedSetting::Speed7 => 6,
+            HWAnimationSpeedSetting::Speed8 => 7,
+            HWAnimationSpeedSetting::Speed9 => 8,
+            HWAnimationSpeedSetting::Level(level) => {
+                (level.get() * (SPEED_STEPS - 1) as f32).round() as usize
+            }
+            HWAnimationSpeedSetting::Period(period) => {
+                (0..self.periods.len()).min_by_key(|i| self.periods[*i].abs_diff(*period))?
+            }
+        })
+    }
+}
+
+/// Protocol whose commands are loaded from a _TOML_ or _JSON_ definition.
+/// All templates are validated while loading; encoding never fails for
+/// the commands the definition provides.
+pub struct DeclarativeProtocol {
+    name: String,
+    light_on: Template,
+    light_off: Template,
+    color: Template,
+    brightness: Option<BrightnessTable>,
+    hw_animate: Option<HWAnimateTable>,
+}
+
+impl DeclarativeProtocol {
+    /// Loads a definition from a file, the format is
+    /// inferred from the extension (`.toml` or `.json`).
+    pub fn from_file<T: AsRef<Path>>(path: T) -> Result<Self, ProtocolDefinitionError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&source),
+            Some("json") => Self::from_json_str(&source),
+            other => Err(ProtocolDefinitionError::UnsupportedFormat(
+                other.unwrap_or("").to_string(),
+            )),
+        }
+    }
+
+    /// Loads a definition from a _TOML_ document.
+    pub fn from_toml_str(source: &str) -> Result<Self, ProtocolDefinitionError> {
+        let definition: Definition =
+            toml::from_str(source).map_err(|e| ProtocolDefinitionError::Parse(e.to_string()))?;
+        Self::from_definition(definition)
+    }
+
+    /// Loads a definition from a _JSON_ document.
+    pub fn from_json_str(source: &str) -> Result<Self, ProtocolDefinitionError> {
+        let definition: Definition = serde_json::from_str(source)
+            .map_err(|e| ProtocolDefinitionError::Parse(e.to_string()))?;
+        Self::from_definition(definition)
+    }
+
+    fn from_definition(definition: Definition) -> Result<Self, ProtocolDefinitionError> {
+        let checksum = definition.checksum;
+
+        let brightness = match definition.brightness {
+            Some(brightness) => Some(BrightnessTable {
+                level: Template::parse(
+                    "brightness.level",
+                    &brightness.level,
+                    &[Token::Level],
+                    checksum,
+                )?,
+                max: brightness.max,
+            }),
+            None => None,
+        };
+
+        let hw_animate = match definition.hw_animate {
+            Some(hw_animate) => {
+                if hw_animate.speeds.len() != SPEED_STEPS {
+                    return Err(ProtocolDefinitionError::InvalidSpeedTable {
+                        expected: SPEED_STEPS,
+                        found: hw_animate.speeds.len(),
+                    });
+                }
+                if !hw_animate.periods.is_empty() && hw_animate.periods.len() != SPEED_STEPS {
+                    return Err(ProtocolDefinitionError::InvalidSpeedTable {
+                        expected: SPEED_STEPS,
+                        found: hw_animate.periods.len(),
+                    });
+                }
+                Some(HWAnimateTable {
+                    pulsating: Template::parse(
+                        "hw_animate.pulsating",
+                        &hw_animate.pulsating,
+                        &[Token::Color, Token::Speed],
+                        checksum,
+                    )?,
+                    colors: hw_animate.colors,
+                    speeds: hw_animate.speeds,
+                    periods: hw_animate
+                        .periods
+                        .into_iter()
+                        .map(Duration::from_millis)
+                        .collect(),
+                })
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            name: definition.name,
+            light_on: Template::parse("light.on", &definition.light.on, &[], checksum)?,
+            light_off: Template::parse("light.off", &definition.light.off, &[], checksum)?,
+            color: Template::parse(
+                "color.rgb",
+                &definition.color.rgb,
+                &[Token::Red, Token::Green, Token::Blue],
+                checksum,
+            )?,
+            brightness,
+            hw_animate,
+        })
+    }
+
+    /// Name of the protocol as declared in its definition
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // `None` if the definition lacks the color or the speed
+    fn _pulsating(
+        &self,
+        color: &HWStaticColorOption,
+        speed: &HWAnimationSpeedSetting,
+    ) -> Option<Vec<u8>> {
+        let table = self.hw_animate.as_ref()?;
+        Some(table.pulsating.render(&Values {
+            color: table.colors.get(color)?,
+            speed: *table.speeds.get(table.speed_index(speed)?)?,
+            ..Default::default()
+        }))
+    }
+}
+
+impl Protocol for DeclarativeProtocol {
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => self.light_on.render(&Values::default()),
+            LightOption::Off => self.light_off.render(&Values::default()),
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let (r, g, b) = option.rgb();
+        self.color.render(&Values {
+            r,
+            g,
+            b,
+            ..Default::default()
+        })
+    }
+
+    fn native_brightness(&self) -> bool {
+        self.brightness.is_some()
+    }
+
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            BrightnessOption::Level(level) => match self.brightness.as_ref() {
+                Some(brightness) => brightness.level.render(&Values {
+                    level: (brightness.max as f32 * level.get()) as u8,
+                    ..Default::default()
+                }),
+                // not declared, white at the given level
+                None => self.color(&ColorOption::RGB(255, 255, 255).scaled(*level)),
+            },
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
+        }
+    }
+
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    fn hw_animate(&self, option: &HWAnimateOption) -> Vec<u8> {
+        // empty when not listed by `hw_animations`, never written
+        match option {
+            HWAnimateOption::Pulsating(color, speed) => self._pulsating(color, speed),
+            _ => None,
+        }
+        .unwrap_or_default()
+    }
+
+    fn animation_period(&self, speed: &HWAnimationSpeedSetting) -> Option<Duration> {
+        let table = self.hw_animate.as_ref()?;
+        table.periods.get(table.speed_index(speed)?).copied()
+    }
+
+    fn hw_animations<'e>(&self, speed: &'e HWAnimationSpeedSetting) -> Vec<HWAnimateOption<'e>> {
+        HWStaticColorOption::all()
+            .iter()
+            .filter(|color| self._pulsating(color, speed).is_some())
+            .map(|color| HWAnimateOption::Pulsating(color, speed))
+            .collect()
+    }
+
+    // the loaded tables are only known per instance and are
+    // applied by `hw_animate`; these fall back to the Triones values
+    fn _animation_speed(setting: &HWAnimationSpeedSetting) -> u8 {
+        GenericRGB::_animation_speed(setting)
+    }
+
+    fn _static_color(color: &HWStaticColorOption) -> u8 {
+        GenericRGB::_static_color(color)
+    }
+}
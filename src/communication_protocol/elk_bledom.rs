@@ -0,0 +1,416 @@
+use std::time::Duration;
+
+#[cfg(feature = "music")]
+use crate::capability::music::{MusicOption, MusicPattern};
+#[cfg(feature = "temperature")]
+use crate::capability::temperature::TemperatureOption;
+use crate::units::{Kelvin, Level};
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::ColorOption,
+        hw_animate::{
+            HWAnimateOption, HWAnimationSpeedSetting, HWBicolorOption, HWSpeedRange,
+            HWStaticColorOption,
+        },
+        light::LightOption,
+    },
+    communication_protocol::{framing::Framing, Protocol},
+};
+
+// every frame is 9 bytes: 0x7E, 0x00, command, 5 parameters, 0xEF
+const FRAME_HEADER: u8 = 0x7E;
+const FRAME_FOOTER: u8 = 0xEF;
+
+// native levels (brightness, speed, white channels) range 0..=100
+const LEVEL_MAX: f32 = 100.0;
+
+/// Protocol spoken by _ELK-BLEDOM_ / _Lotus Lamp_ controllers.
+///
+/// Unlike `GenericRGB`, brightness is a native command, so
+/// `BrightnessOption::Level` does not need a color.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::brightness::BrightnessOption;
+/// use ble_ledly::capability::color::ColorOption;
+/// use ble_ledly::capability::light::LightOption;
+/// use ble_ledly::communication_protocol::{ElkBledom, Protocol};
+/// use ble_ledly::units::Level;
+///
+/// let protocol = ElkBledom::default();
+///
+/// assert_eq!(
+///     protocol.light(&LightOption::On),
+///     vec![0x7E, 0x00, 0x04, 0xF0, 0x00, 0x01, 0xFF, 0x00, 0xEF]
+/// );
+/// assert_eq!(
+///     protocol.color(&ColorOption::RGB(255, 128, 0)),
+///     vec![0x7E, 0x00, 0x05, 0x03, 0xFF, 0x80, 0x00, 0x00, 0xEF]
+/// );
+/// assert_eq!(
+///     protocol.brightness(&BrightnessOption::Level(Level::new(0.5).unwrap())),
+///     vec![0x7E, 0x00, 0x01, 0x32, 0x00, 0x00, 0x00, 0x00, 0xEF]
+/// );
+/// ```
+#[derive(Default)]
+pub struct ElkBledom {}
+
+impl ElkBledom {
+    /// 16-bit UUID of the write characteristic
+    /// exposed by _ELK-BLEDOM_ controllers.
+    pub const WRITE_CHAR_UUID16: u16 = 0xFFF3;
+
+    /// Temperatures of the warm and cold white LEDs, mixed by `Temperature`
+    pub const WARM_WHITE: Kelvin = Kelvin::saturating(2700);
+    pub const COLD_WHITE: Kelvin = Kelvin::saturating(6500);
+
+    /// Native effect speed, `0` (slowest) to `100` (fastest)
+    pub const SPEED_RANGE: HWSpeedRange = HWSpeedRange {
+        slowest: 0,
+        fastest: LEVEL_MAX as u8,
+        slowest_period: Duration::from_millis(10000),
+        fastest_period: Duration::from_millis(500),
+    };
+
+    /// Selects one of the built-in effects.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::elk_bledom::{ElkBledom, ElkBledomEffect};
+    ///
+    /// assert_eq!(
+    ///     ElkBledom::default().effect(&ElkBledomEffect::JumpRGB),
+    ///     vec![0x7E, 0x00, 0x03, 0x87, 0x03, 0x00, 0x00, 0x00, 0xEF]
+    /// );
+    /// ```
+    pub fn effect(&self, effect: &ElkBledomEffect) -> Vec<u8> {
+        ElkBledom::_frame(0x03, [effect.code(), 0x03, 0x00, 0x00, 0x00])
+    }
+
+    /// Sets the speed of the running effect, the period it
+    /// results in is given by `Protocol::animation_period`.
+    ///
+    /// ## Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use ble_ledly::capability::hw_animate::HWAnimationSpeedSetting;
+    /// use ble_ledly::communication_protocol::{ElkBledom, Protocol};
+    ///
+    /// let protocol = ElkBledom::default();
+    /// let speed = HWAnimationSpeedSetting::Period(Duration::from_secs(2));
+    ///
+    /// assert_eq!(
+    ///     protocol.animation_speed(&speed),
+    ///     vec![0x7E, 0x00, 0x02, 0x54, 0x00, 0x00, 0x00, 0x00, 0xEF]
+    /// );
+    /// assert_eq!(protocol.animation_period(&speed), Some(Duration::from_millis(2020)));
+    /// ```
+    pub fn animation_speed(&self, speed: &HWAnimationSpeedSetting) -> Vec<u8> {
+        ElkBledom::_frame(
+            0x02,
+            [ElkBledom::_animation_speed(speed), 0x00, 0x00, 0x00, 0x00],
+        )
+    }
+
+    /// Drives the white channels at the given levels.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::ElkBledom;
+    /// use ble_ledly::units::Level;
+    ///
+    /// assert_eq!(
+    ///     ElkBledom::default().color_temperature(Level::FULL, Level::OFF),
+    ///     vec![0x7E, 0x00, 0x05, 0x02, 0x64, 0x00, 0x00, 0x00, 0xEF]
+    /// );
+    /// ```
+    pub fn color_temperature(&self, warm: Level, cold: Level) -> Vec<u8> {
+        ElkBledom::_frame(
+            0x05,
+            [
+                0x02,
+                ElkBledom::_level(warm),
+                ElkBledom::_level(cold),
+                0x00,
+                0x00,
+            ],
+        )
+    }
+
+    fn _frame(command: u8, parameters: [u8; 5]) -> Vec<u8> {
+        Framing::new()
+            .header(&[FRAME_HEADER, 0x00, command])
+            .footer(&[FRAME_FOOTER])
+            .frame(&parameters)
+    }
+
+    fn _level(level: Level) -> u8 {
+        (level.get() * LEVEL_MAX).round() as u8
+    }
+
+    // microphone patterns share the effect command, from 0x80
+    #[cfg(feature = "music")]
+    fn _music_pattern(pattern: &MusicPattern) -> u8 {
+        match pattern {
+            MusicPattern::Energic => 0x80,
+            MusicPattern::Rhythm => 0x81,
+            MusicPattern::Spectrum => 0x82,
+            MusicPattern::Rolling => 0x83,
+        }
+    }
+}
+
+impl Protocol for ElkBledom {
+    // Light //
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => ElkBledom::_frame(0x04, [0xF0, 0x00, 0x01, 0xFF, 0x00]),
+            LightOption::Off => ElkBledom::_frame(0x04, [0x00, 0x00, 0x00, 0xFF, 0x00]),
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let (r, g, b) = option.rgb();
+        ElkBledom::_frame(0x05, [0x03, r, g, b, 0x00])
+    }
+
+    fn native_brightness(&self) -> bool {
+        true
+    }
+
+    // mixed on the white LEDs, at the given brightness
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::temperature::TemperatureOption;
+    /// use ble_ledly::communication_protocol::{ElkBledom, Protocol};
+    ///
+    /// assert_eq!(
+    ///     ElkBledom::default().white_temperature(&TemperatureOption::Kelvin(ElkBledom::WARM_WHITE)),
+    ///     Some(vec![0x7E, 0x00, 0x05, 0x02, 0x64, 0x00, 0x00, 0x00, 0xEF])
+    /// );
+    /// ```
+    #[cfg(feature = "temperature")]
+    fn white_temperature(&self, option: &TemperatureOption) -> Option<Vec<u8>> {
+        let (warm, cold) = option.white_channels(ElkBledom::WARM_WHITE, ElkBledom::COLD_WHITE);
+        Some(self.color_temperature(warm, cold))
+    }
+
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            BrightnessOption::Level(level) => {
+                ElkBledom::_frame(0x01, [ElkBledom::_level(*level), 0x00, 0x00, 0x00, 0x00])
+            }
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
+        }
+    }
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    // The effect frame only, see `hw_animate_frames` for the speed.
+    fn hw_animate(&self, option: &HWAnimateOption) -> Vec<u8> {
+        let effect = match option {
+            HWAnimateOption::RainbowPulsating(_) => ElkBledomEffect::GradientRGBYCMW,
+            HWAnimateOption::Pulsating(color, _) => match color {
+                HWStaticColorOption::Red => ElkBledomEffect::GradientRed,
+                HWStaticColorOption::Green => ElkBledomEffect::GradientGreen,
+                HWStaticColorOption::Blue => ElkBledomEffect::GradientBlue,
+                HWStaticColorOption::Yellow => ElkBledomEffect::GradientYellow,
+                HWStaticColorOption::Cyan => ElkBledomEffect::GradientCyan,
+                HWStaticColorOption::Purple => ElkBledomEffect::GradientMagenta,
+                HWStaticColorOption::White => ElkBledomEffect::GradientWhite,
+            },
+            HWAnimateOption::PulsatingBicolor(colors, _) => match colors {
+                HWBicolorOption::RedGreen => ElkBledomEffect::GradientRedGreen,
+                HWBicolorOption::RedBlue => ElkBledomEffect::GradientRedBlue,
+                HWBicolorOption::GreenBlue => ElkBledomEffect::GradientGreenBlue,
+            },
+            HWAnimateOption::RainbowFlashing(_) => ElkBledomEffect::BlinkRGBYCMW,
+            HWAnimateOption::Flashing(color, _) => match color {
+                HWStaticColorOption::Red => ElkBledomEffect::BlinkRed,
+                HWStaticColorOption::Green => ElkBledomEffect::BlinkGreen,
+                HWStaticColorOption::Blue => ElkBledomEffect::BlinkBlue,
+                HWStaticColorOption::Yellow => ElkBledomEffect::BlinkYellow,
+                HWStaticColorOption::Cyan => ElkBledomEffect::BlinkCyan,
+                HWStaticColorOption::Purple => ElkBledomEffect::BlinkMagenta,
+                HWStaticColorOption::White => ElkBledomEffect::BlinkWhite,
+            },
+            HWAnimateOption::RainbowJumping(_) => ElkBledomEffect::JumpRGBYCMW,
+        };
+        self.effect(&effect)
+    }
+
+    // The effect speed is a separate command on this controller,
+    // written right after the effect.
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::hw_animate::{HWAnimateOption, HWAnimationSpeedSetting};
+    /// use ble_ledly::communication_protocol::{ElkBledom, Protocol};
+    ///
+    /// let protocol = ElkBledom::default();
+    /// let speed = HWAnimationSpeedSetting::Speed5;
+    ///
+    /// assert_eq!(
+    ///     protocol.hw_animate_frames(&HWAnimateOption::RainbowJumping(&speed)),
+    ///     vec![
+    ///         vec![0x7E, 0x00, 0x03, 0x88, 0x03, 0x00, 0x00, 0x00, 0xEF],
+    ///         protocol.animation_speed(&speed),
+    ///     ]
+    /// );
+    /// ```
+    fn hw_animate_frames(&self, option: &HWAnimateOption) -> Vec<Vec<u8>> {
+        vec![
+            self.hw_animate(option),
+            self.animation_speed(option.speed()),
+        ]
+    }
+
+    //-------//
+    // Music //
+    //-------//
+    // The microphone is switched on first, the pattern and
+    // sensitivity are then separate commands on this controller.
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::music::{MusicOption, MusicPattern};
+    /// use ble_ledly::communication_protocol::{ElkBledom, Protocol};
+    /// use ble_ledly::units::Level;
+    ///
+    /// let protocol = ElkBledom::default();
+    /// let option = MusicOption::On(MusicPattern::Spectrum, Level::new(0.8).unwrap());
+    ///
+    /// assert_eq!(
+    ///     protocol.music(&option),
+    ///     Some(vec![
+    ///         vec![0x7E, 0x00, 0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0xEF],
+    ///         vec![0x7E, 0x00, 0x03, 0x82, 0x04, 0x00, 0x00, 0x00, 0xEF],
+    ///         vec![0x7E, 0x00, 0x06, 0x50, 0x00, 0x00, 0x00, 0x00, 0xEF],
+    ///     ])
+    /// );
+    /// ```
+    #[cfg(feature = "music")]
+    fn music(&self, option: &MusicOption) -> Option<Vec<Vec<u8>>> {
+        Some(match option {
+            MusicOption::On(pattern, sensitivity) => vec![
+                ElkBledom::_frame(0x07, [0x01, 0x00, 0x00, 0x00, 0x00]),
+                ElkBledom::_frame(
+                    0x03,
+                    [ElkBledom::_music_pattern(pattern), 0x04, 0x00, 0x00, 0x00],
+                ),
+                ElkBledom::_frame(
+                    0x06,
+                    [ElkBledom::_level(*sensitivity), 0x00, 0x00, 0x00, 0x00],
+                ),
+            ],
+            MusicOption::Off => vec![ElkBledom::_frame(0x07, [0x00, 0x00, 0x00, 0x00, 0x00])],
+        })
+    }
+
+    fn animation_period(&self, speed: &HWAnimationSpeedSetting) -> Option<Duration> {
+        Some(ElkBledom::SPEED_RANGE.period(ElkBledom::_animation_speed(speed)))
+    }
+
+    fn hw_animations<'e>(&self, speed: &'e HWAnimationSpeedSetting) -> Vec<HWAnimateOption<'e>> {
+        HWAnimateOption::all(speed)
+    }
+
+    fn _animation_speed(setting: &HWAnimationSpeedSetting) -> u8 {
+        match setting {
+            HWAnimationSpeedSetting::Speed1 => 0x0A,
+            HWAnimationSpeedSetting::Speed2 => 0x14,
+            HWAnimationSpeedSetting::Speed3 => 0x1E,
+            HWAnimationSpeedSetting::Speed4 => 0x28,
+            HWAnimationSpeedSetting::Speed5 => 0x32,
+            HWAnimationSpeedSetting::Speed6 => 0x3C,
+            HWAnimationSpeedSetting::Speed7 => 0x46,
+            HWAnimationSpeedSetting::Speed8 => 0x50,
+            HWAnimationSpeedSetting::Speed9 => 0x5A,
+            HWAnimationSpeedSetting::Level(level) => ElkBledom::SPEED_RANGE.native_level(*level),
+            HWAnimationSpeedSetting::Period(period) => {
+                ElkBledom::SPEED_RANGE.native_period(*period)
+            }
+        }
+    }
+
+    fn _static_color(color: &HWStaticColorOption) -> u8 {
+        match color {
+            HWStaticColorOption::Red => ElkBledomEffect::GradientRed.code(),
+            HWStaticColorOption::Green => ElkBledomEffect::GradientGreen.code(),
+            HWStaticColorOption::Blue => ElkBledomEffect::GradientBlue.code(),
+            HWStaticColorOption::Yellow => ElkBledomEffect::GradientYellow.code(),
+            HWStaticColorOption::Cyan => ElkBledomEffect::GradientCyan.code(),
+            HWStaticColorOption::Purple => ElkBledomEffect::GradientMagenta.code(),
+            HWStaticColorOption::White => ElkBledomEffect::GradientWhite.code(),
+        }
+    }
+}
+
+/// Built-in effects of _ELK-BLEDOM_ controllers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ElkBledomEffect {
+    StaticRed,
+    StaticGreen,
+    StaticBlue,
+    StaticYellow,
+    StaticCyan,
+    StaticMagenta,
+    StaticWhite,
+    JumpRGB,
+    JumpRGBYCMW,
+    GradientRGB,
+    GradientRGBYCMW,
+    GradientRed,
+    GradientGreen,
+    GradientBlue,
+    GradientYellow,
+    GradientCyan,
+    GradientMagenta,
+    GradientWhite,
+    GradientRedGreen,
+    GradientRedBlue,
+    GradientGreenBlue,
+    BlinkRGBYCMW,
+    BlinkRed,
+    BlinkGreen,
+    BlinkBlue,
+    BlinkYellow,
+    BlinkCyan,
+    BlinkMagenta,
+    BlinkWhite,
+}
+
+impl ElkBledomEffect {
+    fn code(&self) -> u8 {
+        match self {
+            ElkBledomEffect::StaticRed => 0x80,
+            ElkBledomEffect::StaticGreen => 0x81,
+            ElkBledomEffect::StaticBlue => 0x82,
+            ElkBledomEffect::StaticYellow => 0x83,
+            ElkBledomEffect::StaticCyan => 0x84,
+            ElkBledomEffect::StaticMagenta => 0x85,
+            ElkBledomEffect::StaticWhite => 0x86,
+            ElkBledomEffect::JumpRGB => 0x87,
+            ElkBledomEffect::JumpRGBYCMW => 0x88,
+            ElkBledomEffect::GradientRGB => 0x89,
+            ElkBledomEffect::GradientRGBYCMW => 0x8A,
+            ElkBledomEffect::GradientRed => 0x8B,
+            ElkBledomEffect::GradientGreen => 0x8C,
+            ElkBledomEffect::GradientBlue => 0x8D,
+            ElkBledomEffect::GradientYellow => 0x8E,
+            ElkBledomEffect::GradientCyan => 0x8F,
+            ElkBledomEffect::GradientMagenta => 0x90,
+            ElkBledomEffect::GradientWhite => 0x91,
+            ElkBledomEffect::GradientRedGreen => 0x92,
+            ElkBledomEffect::GradientRedBlue => 0x93,
+            ElkBledomEffect::GradientGreenBlue => 0x94,
+            ElkBledomEffect::BlinkRGBYCMW => 0x95,
+            ElkBledomEffect::BlinkRed => 0x96,
+            ElkBledomEffect::BlinkGreen => 0x97,
+            ElkBledomEffect::BlinkBlue => 0x98,
+            ElkBledomEffect::BlinkYellow => 0x99,
+            ElkBledomEffect::BlinkCyan => 0x9A,
+            ElkBledomEffect::BlinkMagenta => 0x9B,
+            ElkBledomEffect::BlinkWhite => 0x9C,
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Building blocks shared by byte-oriented protocols: headers and
+//! footers, sequence counters, length prefixes, checksums and padding.
+//!
+//! A `Framing` describes the layout wrapped around the payload of a command,
+//!
+//! `header | sequence | length | payload | padding | checksum | footer`
+//!
+//! where every part is optional; the same `Framing` builds outgoing
+//! frames and validates the responses of the device.
+//!
+//! ## Examples
+//! ```
+//! use ble_ledly::communication_protocol::framing::{Checksum, Framing, LengthPrefix};
+//!
+//! let framing = Framing::new()
+//!     .header(&[0xAA])
+//!     .sequence()
+//!     .length(LengthPrefix::U8)
+//!     .checksum(Checksum::Sum8)
+//!     .footer(&[0x55]);
+//!
+//! let frame = framing.frame(&[0x01, 0x02]);
+//! assert_eq!(frame, vec![0xAA, 0x00, 0x02, 0x01, 0x02, 0xAF, 0x55]);
+//!
+//! let parsed = framing.parse(&frame).unwrap();
+//! assert_eq!(parsed.sequence, Some(0x00));
+//! assert_eq!(parsed.payload, vec![0x01, 0x02]);
+//!
+//! // the counter moves on at every frame
+//! assert_eq!(framing.frame(&[0x01, 0x02])[1], 0x01);
+//! ```
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::FramingError;
+
+/// Checksum algorithms, computed over the frame
+/// from `Framing::checksum_from` up to the checksum itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    /// XOR of all the bytes
+    Xor,
+    /// Sum of all the bytes, modulo 256
+    Sum8,
+    /// _CRC-8/SMBUS_ (polynomial `0x07`, initial value `0x00`)
+    Crc8,
+    /// _CRC-16/MODBUS_ (polynomial `0xA001` reflected, initial
+    /// value `0xFFFF`), big endian
+    Crc16,
+}
+
+/// Length field preceding the payload,
+/// holding the number of bytes of the payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthPrefix {
+    U8,
+    U16Be,
+    U16Le,
+}
+
+/// Frame validated by `Framing::parse`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedFrame {
+    pub sequence: Option<u8>,
+    pub payload: Vec<u8>,
+}
+
+/// Layout of the frames of a protocol, see the module documentation.
+#[derive(Debug, Default)]
+pub struct Framing {
+    header: Vec<u8>,
+    footer: Vec<u8>,
+    sequence: Option<AtomicU8>,
+    length: Option<LengthPrefix>,
+    checksum: Option<Checksum>,
+    checksum_from: usize,
+    // total frame size and padding byte
+    padding: Option<(usize, u8)>,
+}
+
+impl Checksum {
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::framing::Checksum;
+    ///
+    /// let bytes = b"123456789";
+    /// assert_eq!(Checksum::Xor.compute(bytes), vec![0x31]);
+    /// assert_eq!(Checksum::Sum8.compute(bytes), vec![0xDD]);
+    /// assert_eq!(Checksum::Crc8.compute(bytes), vec![0xF4]);
+    /// assert_eq!(Checksum::Crc16.compute(bytes), vec![0x4B, 0x37]);
+    /// ```
+    pub fn compute(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Checksum::Xor => vec![bytes.iter().fold(0, |acc, b| acc ^ b)],
+            Checksum::Sum8 => vec![bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))],
+            Checksum::Crc8 => vec![bytes.iter().fold(0x00u8, |crc, byte| {
+                (0..8).fold(crc ^ byte, |crc, _| {
+                    if crc & 0x80 != 0 {
+                        (crc << 1) ^ 0x07
+                    } else {
+                        crc << 1
+                    }
+                })
+            })],
+            Checksum::Crc16 => bytes
+                .iter()
+                .fold(0xFFFFu16, |crc, byte| {
+                    (0..8).fold(crc ^ *byte as u16, |crc, _| {
+                        if crc & 0x0001 != 0 {
+                            (crc >> 1) ^ 0xA001
+                        } else {
+                            crc >> 1
+                        }
+                    })
+                })
+                .to_be_bytes()
+                .to_vec(),
+        }
+    }
+
+    /// Number of bytes of the checksum
+    pub fn size(&self) -> usize {
+        match self {
+            Checksum::Xor | Checksum::Sum8 | Checksum::Crc8 => 1,
+            Checksum::Crc16 => 2,
+        }
+    }
+}
+
+impl LengthPrefix {
+    fn _encode(&self, length: usize) -> Vec<u8> {
+        match self {
+            LengthPrefix::U8 => vec![length as u8],
+            LengthPrefix::U16Be => (length as u16).to_be_bytes().to_vec(),
+            LengthPrefix::U16Le => (length as u16).to_le_bytes().to_vec(),
+        }
+    }
+
+    fn _decode(&self, bytes: &[u8]) -> Option<usize> {
+        Some(match self {
+            LengthPrefix::U8 => *bytes.first()? as usize,
+            LengthPrefix::U16Be => u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]) as usize,
+            LengthPrefix::U16Le => u16::from_le_bytes([*bytes.first()?, *bytes.get(1)?]) as usize,
+        })
+    }
+
+    fn _len(&self) -> usize {
+        match self {
+            LengthPrefix::U8 => 1,
+            LengthPrefix::U16Be | LengthPrefix::U16Le => 2,
+        }
+    }
+}
+
+impl Framing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //---------//
+    // Builder //
+    //---------//
+    /// Fixed bytes starting every frame
+    pub fn header(mut self, bytes: &[u8]) -> Self {
+        self.header = bytes.to_vec();
+        self
+    }
+    /// Fixed bytes ending every frame
+    pub fn footer(mut self, bytes: &[u8]) -> Self {
+        self.footer = bytes.to_vec();
+        self
+    }
+    /// Wrapping sequence counter after the header,
+    /// incremented at every frame built
+    pub fn sequence(mut self) -> Self {
+        self.sequence = Some(AtomicU8::new(0));
+        self
+    }
+    /// Length of the payload, before the payload
+    pub fn length(mut self, prefix: LengthPrefix) -> Self {
+        self.length = Some(prefix);
+        self
+    }
+    /// Checksum before the footer, covering the frame from
+    /// the `checksum_from` offset (`0` by default)
+    pub fn checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+    /// Offset of the first byte covered by the checksum
+    pub fn checksum_from(mut self, offset: usize) -> Self {
+        self.checksum_from = offset;
+        self
+    }
+    /// Pads shorter frames with `byte` after the payload,
+    /// up to `size` bytes (checksum and footer included)
+    pub fn pad_to(mut self, size: usize, byte: u8) -> Self {
+        self.padding = Some((size, byte));
+        self
+    }
+
+    //-------//
+    // Frame //
+    //-------//
+    /// Wraps the payload into a frame
+    pub fn frame(&self, payload: &[u8]) -> Vec<u8> {
+        let mut frame = self.header.clone();
+        if let Some(sequence) = self.sequence.as_ref() {
+            frame.push(sequence.fetch_add(1, Ordering::Relaxed));
+        }
+        if let Some(length) = self.length {
+            frame.extend_from_slice(&length._encode(payload.len()));
+        }
+        frame.extend_from_slice(payload);
+
+        if let Some((size, byte)) = self.padding {
+            let trailer = self.checksum.map_or(0, |c| c.size()) + self.footer.len();
+            if frame.len() + trailer < size {
+                frame.resize(size - trailer, byte);
+            }
+        }
+        if let Some(checksum) = self.checksum {
+            let covered = frame.get(self.checksum_from..).unwrap_or_default();
+            frame.extend_from_slice(&checksum.compute(covered));
+        }
+        frame.extend_from_slice(&self.footer);
+        frame
+    }
+
+    /// Validates a frame received from the device and extracts its payload;
+    /// without a length prefix the payload includes the padding, if any.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::framing::{Checksum, Framing};
+    /// use ble_ledly::error::FramingError;
+    ///
+    /// let framing = Framing::new().header(&[0x33]).checksum(Checksum::Xor);
+    ///
+    /// assert_eq!(framing.parse(&[0x33, 0x01, 0x32]).unwrap().payload, vec![0x01]);
+    /// assert!(matches!(
+    ///     framing.parse(&[0x33, 0x01, 0x00]),
+    ///     Err(FramingError::InvalidChecksum)
+    /// ));
+    /// ```
+    pub fn parse(&self, frame: &[u8]) -> Result<ParsedFrame, FramingError> {
+        let checksum_len = self.checksum.map_or(0, |c| c.size());
+        let overhead = self.header.len()
+            + self.sequence.as_ref().map_or(0, |_| 1)
+            + self.length.map_or(0, |l| l._len())
+            + checksum_len
+            + self.footer.len();
+        if frame.len() < overhead {
+            return Err(FramingError::TooShort);
+        }
+        if let Some((size, _)) = self.padding {
+            if frame.len() < size {
+                return Err(FramingError::TooShort);
+            }
+        }
+
+        if !frame.starts_with(&self.header) {
+            return Err(FramingError::InvalidHeader);
+        }
+        if !frame.ends_with(&self.footer) {
+            return Err(FramingError::InvalidFooter);
+        }
+
+        // frame without the footer, then without the checksum
+        let body = &frame[..frame.len() - self.footer.len()];
+        let body = match self.checksum {
+            Some(checksum) => {
+                let (covered, received) = body.split_at(body.len() - checksum_len);
+                let covered_from = covered.get(self.checksum_from..).unwrap_or_default();
+                if checksum.compute(covered_from) != received {
+                    return Err(FramingError::InvalidChecksum);
+                }
+                covered
+            }
+            None => body,
+        };
+
+        let mut position = self.header.len();
+        let sequence = self.sequence.as_ref().map(|_| body[position]);
+        position += sequence.map_or(0, |_| 1);
+
+        let payload = match self.length {
+            Some(length) => {
+                let declared = length
+                    ._decode(&body[position..])
+                    .ok_or(FramingError::TooShort)?;
+                position += length._len();
+                body.get(position..position + declared)
+                    .ok_or(FramingError::InvalidLength)?
+            }
+            None => &body[position..],
+        };
+
+        Ok(ParsedFrame {
+            sequence,
+            payload: payload.to_vec(),
+        })
+    }
+}
@@ -1,23 +1,150 @@
+use std::time::Duration;
+
+#[cfg(feature = "music")]
+use crate::capability::music::{MusicOption, MusicPattern};
 use crate::{
     capability::{
         brightness::BrightnessOption,
-        color::ColorOption,
+        color::{ChannelLayout, ColorOption},
         hw_animate::{
-            HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption,
+            HWAnimateOption, HWAnimationSpeedSetting, HWBicolorOption, HWSpeedRange,
+            HWStaticColorOption,
         },
         light::LightOption,
     },
-    communication_protocol::Protocol,
+    communication_protocol::{framing::Framing, DeviceState, Protocol, StateProtocol},
 };
 
-pub struct GenericRGB {}
+// every command is wrapped in a fixed header and footer
+// depending on the command kind
+const POWER: (u8, u8) = (0xCC, 0x33);
+const COLOR: (u8, u8) = (0x56, 0xAA);
+const HW_ANIMATE: (u8, u8) = (0xBB, 0x44);
+const STATE_QUERY: (u8, u8) = (0xEF, 0x77);
+// microphone mode: on/off, pattern, sensitivity
+#[cfg(feature = "music")]
+const MUSIC: (u8, u8) = (0x73, 0x37);
+// state report: device type, power, mode, _,
+// speed, r, g, b, white, version
+const STATE_REPORT: (u8, u8) = (0x66, 0x99);
 
-impl Default for GenericRGB {
-    fn default() -> Self {
-        Self {}
-    }
+const POWER_ON: u8 = 0x23;
+const POWER_OFF: u8 = 0x24;
+
+/// Protocol spoken by _Triones_ / _QHM-_ generic RGB controllers.
+///
+/// ## Examples
+/// ```
+/// use std::time::Duration;
+/// use ble_ledly::capability::color::{ChannelLayout, ColorOption};
+/// use ble_ledly::capability::hw_animate::{
+///     HWAnimateOption, HWAnimationSpeedSetting, HWBicolorOption, HWStaticColorOption,
+/// };
+/// use ble_ledly::capability::light::LightOption;
+/// use ble_ledly::communication_protocol::{GenericRGB, Protocol, StateProtocol};
+///
+/// let protocol = GenericRGB::default();
+///
+/// assert_eq!(protocol.light(&LightOption::On), vec![0xCC, 0x23, 0x33]);
+/// assert_eq!(
+///     protocol.color(&ColorOption::RGB(255, 128, 0)),
+///     vec![0x56, 0xFF, 0x80, 0x00, 0x00, 0xF0, 0xAA]
+/// );
+/// assert_eq!(
+///     protocol.hw_animate(&HWAnimateOption::Pulsating(
+///         &HWStaticColorOption::Red,
+///         &HWAnimationSpeedSetting::Speed9
+///     )),
+///     vec![0xBB, 0x26, 0x01, 0x44]
+/// );
+/// assert_eq!(
+///     protocol.hw_animate(&HWAnimateOption::PulsatingBicolor(
+///         &HWBicolorOption::GreenBlue,
+///         &HWAnimationSpeedSetting::Speed1
+///     )),
+///     vec![0xBB, 0x2F, 0x1F, 0x44]
+/// );
+/// assert_eq!(
+///     protocol.hw_animate(&HWAnimateOption::Flashing(
+///         &HWStaticColorOption::White,
+///         &HWAnimationSpeedSetting::Speed9
+///     )),
+///     vec![0xBB, 0x37, 0x01, 0x44]
+/// );
+///
+/// // every built-in mode, 0x25..=0x38
+/// let speed = HWAnimationSpeedSetting::Speed9;
+/// let modes: Vec<u8> = protocol
+///     .hw_animations(&speed)
+///     .iter()
+///     .map(|mode| protocol.hw_animate(mode)[1])
+///     .collect();
+/// assert_eq!(modes, (0x25..=0x38).collect::<Vec<u8>>());
+///
+/// // continuous speed, mapped onto the native speed byte
+/// let speed = HWAnimationSpeedSetting::Period(Duration::from_millis(1250));
+/// assert_eq!(
+///     protocol.hw_animate(&HWAnimateOption::RainbowJumping(&speed)),
+///     vec![0xBB, 0x38, 0x06, 0x44]
+/// );
+/// assert_eq!(protocol.animation_period(&speed), Some(Duration::from_millis(1200)));
+/// assert_eq!(protocol.query_state(), vec![0xEF, 0x01, 0x77]);
+///
+/// // controllers with a white channel drive it alone in white mode
+/// let protocol = GenericRGB::with_layout(ChannelLayout::RGBW);
+/// assert_eq!(
+///     protocol.color(&ColorOption::RGBW(0, 0, 0, 200)),
+///     vec![0x56, 0x00, 0x00, 0x00, 0xC8, 0x0F, 0xAA]
+/// );
+/// ```
+#[derive(Default)]
+pub struct GenericRGB {
+    layout: ChannelLayout,
 }
 
+impl GenericRGB {
+    /// 16-bit UUID of the write characteristic
+    pub const WRITE_CHAR_UUID16: u16 = 0xFFD9;
+
+    /// 16-bit UUID of the characteristic notifying state reports
+    pub const NOTIFY_CHAR_UUID16: u16 = 0xFFD4;
+
+    /// Speed byte of the built-in modes, a delay of
+    /// roughly 200ms per unit between animation steps
+    pub const SPEED_RANGE: HWSpeedRange = HWSpeedRange {
+        slowest: 0x1F,
+        fastest: 0x01,
+        slowest_period: Duration::from_millis(6200),
+        fastest_period: Duration::from_millis(200),
+    };
+
+    /// Controller with the given color channels; the
+    /// white channel is driven in white mode only.
+    pub fn with_layout(layout: ChannelLayout) -> Self {
+        Self { layout }
+    }
+
+    fn _framing((header, footer): (u8, u8)) -> Framing {
+        Framing::new().header(&[header]).footer(&[footer])
+    }
+
+    // built-in mode code, 0x25..=0x38
+    pub(crate) fn _mode(option: &HWAnimateOption) -> u8 {
+        match option {
+            HWAnimateOption::RainbowPulsating(_) => 0x25,
+            HWAnimateOption::Pulsating(color, _) => GenericRGB::_static_color(color),
+            HWAnimateOption::PulsatingBicolor(colors, _) => match colors {
+                HWBicolorOption::RedGreen => 0x2D,
+                HWBicolorOption::RedBlue => 0x2E,
+                HWBicolorOption::GreenBlue => 0x2F,
+            },
+            HWAnimateOption::RainbowFlashing(_) => 0x30,
+            // flashing modes follow the pulsating ones, in the same color order
+            HWAnimateOption::Flashing(color, _) => GenericRGB::_static_color(color) + 0x0B,
+            HWAnimateOption::RainbowJumping(_) => 0x38,
+        }
+    }
+}
 
 impl Protocol for GenericRGB {
     // Light //
@@ -26,43 +153,92 @@ impl Protocol for GenericRGB {
         // HWspecific effects are turn_on
         // use legacy mode instead
         match option {
-            LightOption::On => vec![0xcc, 0x23, 0x33],
-            LightOption::Off => vec![0xcc, 0x24, 0x33],
+            LightOption::On => GenericRGB::_framing(POWER).frame(&[POWER_ON]),
+            LightOption::Off => GenericRGB::_framing(POWER).frame(&[POWER_OFF]),
         }
     }
 
     fn color(&self, option: &ColorOption) -> Vec<u8> {
-        match option {
-            ColorOption::RGB(r, g, b) => vec![0x56, *r, *g, *b, 0x00, 0xF0, 0xAA],
+        // color and white modes are exclusive
+        match option.to_layout(ChannelLayout::RGBW) {
+            ColorOption::RGBW(0, 0, 0, w) if self.layout != ChannelLayout::RGB => {
+                GenericRGB::_framing(COLOR).frame(&[0x00, 0x00, 0x00, w, 0x0F])
+            }
+            _ => {
+                let (r, g, b) = option.rgb();
+                GenericRGB::_framing(COLOR).frame(&[r, g, b, 0x00, 0xF0])
+            }
         }
     }
 
+    fn channel_layout(&self) -> ChannelLayout {
+        self.layout
+    }
+
     fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
         match option {
-            BrightnessOption::Level(_level) => {
-                unimplemented!("Brightness without ColorOption, not supported yet")
+            // no brightness command, white at the given level
+            BrightnessOption::Level(level) => {
+                self.color(&ColorOption::RGB(255, 255, 255).scaled(*level))
             }
-            BrightnessOption::LevelWithColor(level, color) => match color {
-                ColorOption::RGB(r, g, b) => {
-                    self.color(&ColorOption::RGB((*r as f32 * level) as u8, (*g as f32 * level) as u8, (*b as f32 * level) as u8))
-                }
-            },
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
         }
     }
     //-----------//
     // HWAnimate //
     //-----------//
     fn hw_animate(&self, option: &HWAnimateOption) -> Vec<u8> {
-        match option {
-            HWAnimateOption::Pulsating(color, speed) => {
-                vec![
-                    0xBB,
-                    GenericRGB::_static_color(&color),
-                    GenericRGB::_animation_speed(&speed),
-                    0x44,
-                ]
-            }
-        }
+        GenericRGB::_framing(HW_ANIMATE).frame(&[
+            GenericRGB::_mode(option),
+            GenericRGB::_animation_speed(option.speed()),
+        ])
+    }
+
+    fn animation_period(&self, speed: &HWAnimationSpeedSetting) -> Option<Duration> {
+        Some(GenericRGB::SPEED_RANGE.period(GenericRGB::_animation_speed(speed)))
+    }
+
+    fn hw_animations<'e>(&self, speed: &'e HWAnimationSpeedSetting) -> Vec<HWAnimateOption<'e>> {
+        HWAnimateOption::all(speed)
+    }
+
+    //-------//
+    // Music //
+    //-------//
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::music::{MusicOption, MusicPattern};
+    /// use ble_ledly::communication_protocol::{GenericRGB, Protocol};
+    /// use ble_ledly::units::Level;
+    ///
+    /// let protocol = GenericRGB::default();
+    /// let option = MusicOption::On(MusicPattern::Rhythm, Level::new(0.5).unwrap());
+    ///
+    /// assert_eq!(
+    ///     protocol.music(&option),
+    ///     Some(vec![vec![0x73, 0x01, 0x02, 0x32, 0x37]])
+    /// );
+    /// assert_eq!(
+    ///     protocol.music(&MusicOption::Off),
+    ///     Some(vec![vec![0x73, 0x00, 0x00, 0x00, 0x37]])
+    /// );
+    /// ```
+    #[cfg(feature = "music")]
+    fn music(&self, option: &MusicOption) -> Option<Vec<Vec<u8>>> {
+        let payload = match option {
+            MusicOption::On(pattern, sensitivity) => [
+                0x01,
+                match pattern {
+                    MusicPattern::Energic => 0x01,
+                    MusicPattern::Rhythm => 0x02,
+                    MusicPattern::Spectrum => 0x03,
+                    MusicPattern::Rolling => 0x04,
+                },
+                (sensitivity.get() * 100.0).round() as u8,
+            ],
+            MusicOption::Off => [0x00, 0x00, 0x00],
+        };
+        Some(vec![GenericRGB::_framing(MUSIC).frame(&payload)])
     }
 
     fn _animation_speed(setting: &HWAnimationSpeedSetting) -> u8 {
@@ -76,6 +252,10 @@ impl Protocol for GenericRGB {
             HWAnimationSpeedSetting::Speed7 => 0x0C,
             HWAnimationSpeedSetting::Speed8 => 0x05,
             HWAnimationSpeedSetting::Speed9 => 0x01,
+            HWAnimationSpeedSetting::Level(level) => GenericRGB::SPEED_RANGE.native_level(*level),
+            HWAnimationSpeedSetting::Period(period) => {
+                GenericRGB::SPEED_RANGE.native_period(*period)
+            }
         }
     }
 
@@ -84,6 +264,50 @@ impl Protocol for GenericRGB {
             HWStaticColorOption::Red => 0x26,
             HWStaticColorOption::Green => 0x27,
             HWStaticColorOption::Blue => 0x28,
+            HWStaticColorOption::Yellow => 0x29,
+            HWStaticColorOption::Cyan => 0x2A,
+            HWStaticColorOption::Purple => 0x2B,
+            HWStaticColorOption::White => 0x2C,
+        }
+    }
+}
+
+impl StateProtocol for GenericRGB {
+    fn query_state(&self) -> Vec<u8> {
+        GenericRGB::_framing(STATE_QUERY).frame(&[0x01])
+    }
+
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::capability::light::LightOption;
+    /// use ble_ledly::communication_protocol::{GenericRGB, StateProtocol};
+    ///
+    /// let report = [
+    ///     0x66, 0x15, 0x23, 0x41, 0x20, 0x10, 0xFF, 0x80, 0x00, 0x00, 0x03, 0x99,
+    /// ];
+    /// let state = GenericRGB::default().decode_state(&report).unwrap();
+    ///
+    /// assert_eq!(state.light, LightOption::On);
+    /// assert_eq!(state.mode, 0x41);
+    /// assert_eq!(state.speed, 0x10);
+    /// assert_eq!(state.color, ColorOption::RGB(0xFF, 0x80, 0x00));
+    /// assert_eq!(state.white, 0x00);
+    /// ```
+    fn decode_state(&self, payload: &[u8]) -> Option<DeviceState> {
+        let report = GenericRGB::_framing(STATE_REPORT).parse(payload).ok()?;
+        match report.payload[..] {
+            [_, power, mode, _, speed, r, g, b, white, _] => Some(DeviceState {
+                light: match power {
+                    POWER_ON => LightOption::On,
+                    _ => LightOption::Off,
+                },
+                mode,
+                speed,
+                color: ColorOption::RGB(r, g, b),
+                white,
+            }),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,320 @@
+use std::time::Duration;
+
+use btleplug::api::Peripheral as _;
+use tokio::task::JoinHandle;
+use tokio::time;
+
+#[cfg(feature = "temperature")]
+use crate::capability::temperature::TemperatureOption;
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::ColorOption,
+        hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
+        light::LightOption,
+    },
+    communication_protocol::{
+        framing::{Checksum, Framing},
+        Protocol,
+    },
+    device::{write_packets, CharTarget, Device},
+    error::BluetoothError,
+    units::Kelvin,
+};
+
+// command packets: 0x33, command, payload, zero padding, xor checksum
+const PACKET_LEN: usize = 20;
+const COMMAND: u8 = 0x33;
+const KEEP_ALIVE: u8 = 0xAA;
+
+const POWER: u8 = 0x01;
+const BRIGHTNESS: u8 = 0x04;
+const COLOR: u8 = 0x05;
+
+const COLOR_MANUAL: u8 = 0x02;
+const COLOR_SCENE: u8 = 0x04;
+
+const KELVIN_MIN: u16 = 2000;
+const KELVIN_MAX: u16 = 9000;
+
+/// Protocol spoken by _Govee_ H6xxx BLE strips and bulbs.
+///
+/// Govee devices drop the connection if they don't receive a packet
+/// every few seconds; see `Govee::keep_alive`.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::color::ColorOption;
+/// use ble_ledly::capability::light::LightOption;
+/// use ble_ledly::communication_protocol::{Govee, Protocol};
+///
+/// let protocol = Govee::default();
+///
+/// let mut on = vec![0x33, 0x01, 0x01];
+/// on.resize(19, 0x00);
+/// on.push(0x33);
+/// assert_eq!(protocol.light(&LightOption::On), on);
+///
+/// let mut red = vec![0x33, 0x05, 0x02, 0xFF, 0x00, 0x00];
+/// red.resize(19, 0x00);
+/// red.push(0xCB);
+/// assert_eq!(protocol.color(&ColorOption::RGB(255, 0, 0)), red);
+/// ```
+#[derive(Default)]
+pub struct Govee {}
+
+impl Govee {
+    /// 128-bit UUID of the write characteristic
+    /// exposed by _Govee_ devices.
+    pub const WRITE_CHAR_UUID: u128 = 0x00010203_0405_0607_0809_0a0b0c0d2b11;
+
+    /// Interval between two keep-alive packets.
+    pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// White light at the given temperature, clamped
+    /// to the supported `2000..=9000` Kelvin.
+    pub fn white(&self, kelvin: Kelvin) -> Vec<u8> {
+        let (r, g, b) = ColorOption::from_kelvin(kelvin.get().clamp(KELVIN_MIN, KELVIN_MAX)).rgb();
+        Govee::_packet(&[
+            COMMAND,
+            COLOR,
+            COLOR_MANUAL,
+            0xFF,
+            0xFF,
+            0xFF,
+            0x01,
+            r,
+            g,
+            b,
+        ])
+    }
+
+    /// Starts one of the built-in scenes by its id.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::Govee;
+    ///
+    /// let mut scene = vec![0x33, 0x05, 0x04, 0x0B, 0x00];
+    /// scene.resize(19, 0x00);
+    /// scene.push(0x39);
+    /// assert_eq!(Govee::default().scene(0x000B), scene);
+    /// ```
+    pub fn scene(&self, id: u16) -> Vec<u8> {
+        let [low, high] = id.to_le_bytes();
+        Govee::_packet(&[COMMAND, COLOR, COLOR_SCENE, low, high])
+    }
+
+    /// Packet expected periodically by the device to keep the connection alive.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::Govee;
+    ///
+    /// let mut keep_alive = vec![0xAA, 0x01];
+    /// keep_alive.resize(19, 0x00);
+    /// keep_alive.push(0xAB);
+    /// assert_eq!(Govee::default().keep_alive_packet(), keep_alive);
+    /// ```
+    pub fn keep_alive_packet(&self) -> Vec<u8> {
+        Govee::_packet(&[KEEP_ALIVE, 0x01])
+    }
+
+    /// Spawns a task sending a keep-alive packet to the device every
+    /// `Govee::KEEP_ALIVE_INTERVAL`. The task ends as soon as the device
+    /// disconnects, a write fails, or the returned `KeepAlive` is dropped.
+    /// Must be called from within a _tokio_ runtime.
+    ///
+    /// Packets go through the `Transport` of the device: they are split by
+    /// its MTU, paced, and never interleaved with the packets of other commands.
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    let protocol = Govee::default();
+    ///    let _keep_alive = protocol.keep_alive(light)?;
+    ///    light.turn_on(&protocol).await?;
+    /// ```
+    pub fn keep_alive<D: Device>(&self, device: &D) -> Result<KeepAlive, BluetoothError> {
+        let peripheral = device
+            .peripheral()
+            .ok_or(BluetoothError::InvalidPeripheralReference)?
+            .clone();
+        let characteristic = device.resolve_target(&CharTarget::Default)?;
+        let packets = self.packets(&self.keep_alive_packet(), device.transport().mtu());
+        let pacing = device.transport().pacing();
+        let write_lock = device.transport().shared_lock();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = time::interval(Govee::KEEP_ALIVE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if !peripheral.is_connected().await.unwrap_or(false) {
+                    break;
+                }
+                // not interleaved with the commands written meanwhile
+                let _guard = write_lock.lock().await;
+                if write_packets(&peripheral, &characteristic, &packets, pacing)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        Ok(KeepAlive { handle })
+    }
+
+    fn _packet(bytes: &[u8]) -> Vec<u8> {
+        Framing::new()
+            .pad_to(PACKET_LEN, 0x00)
+            .checksum(Checksum::Xor)
+            .frame(bytes)
+    }
+}
+
+/// Handle to a running keep-alive task,
+/// the task is stopped when the handle is dropped.
+pub struct KeepAlive {
+    handle: JoinHandle<()>,
+}
+
+impl KeepAlive {
+    /// Whether the task is still sending keep-alive packets
+    pub fn is_running(&self) -> bool {
+        !self.handle.is_finished()
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl Protocol for Govee {
+    // Light //
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => Govee::_packet(&[COMMAND, POWER, 0x01]),
+            LightOption::Off => Govee::_packet(&[COMMAND, POWER, 0x00]),
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let (r, g, b) = option.rgb();
+        Govee::_packet(&[COMMAND, COLOR, COLOR_MANUAL, r, g, b])
+    }
+
+    fn native_brightness(&self) -> bool {
+        true
+    }
+
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            BrightnessOption::Level(level) => {
+                Govee::_packet(&[COMMAND, BRIGHTNESS, (level.get() * 255.0).round() as u8])
+            }
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
+        }
+    }
+    // the white mode has no brightness, fall back to the color otherwise
+    #[cfg(feature = "temperature")]
+    fn white_temperature(&self, option: &TemperatureOption) -> Option<Vec<u8>> {
+        match option {
+            TemperatureOption::Kelvin(kelvin) => Some(self.white(*kelvin)),
+            TemperatureOption::KelvinWithBrightness(_, _) => None,
+        }
+    }
+
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    // Govee animations are scenes identified by device-specific
+    // ids, use `Govee::scene` instead.
+    fn hw_animate(&self, _option: &HWAnimateOption) -> Vec<u8> {
+        // none listed by `hw_animations`, never written
+        Vec::new()
+    }
+
+    fn _animation_speed(_setting: &HWAnimationSpeedSetting) -> u8 {
+        0x00
+    }
+
+    fn _static_color(_color: &HWStaticColorOption) -> u8 {
+        0x00
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::communication_protocol::hex;
+    use crate::units::{Kelvin, Level};
+
+    // Packets of the Govee Home app to H6xxx strips, as published by the
+    // community reverse engineering of the protocol (20 bytes, xor checksum)
+    const POWER_ON: &str = "3301010000000000000000000000000000000033";
+    const POWER_OFF: &str = "3301000000000000000000000000000000000032";
+    const BRIGHTNESS_FULL: &str = "3304ff00000000000000000000000000000000c8";
+    const BRIGHTNESS_OFF: &str = "3304000000000000000000000000000000000037";
+    const COLOR_RED: &str = "330502ff000000000000000000000000000000cb";
+    const COLOR_BLUE: &str = "3305020000ff00000000000000000000000000cb";
+    const SCENE_CANDLELIGHT: &str = "330504090000000000000000000000000000003b";
+    const KEEP_ALIVE: &str = "aa010000000000000000000000000000000000ab";
+
+    #[test]
+    fn encodes_power() {
+        let protocol = Govee::default();
+        assert_eq!(protocol.light(&LightOption::On), hex(POWER_ON));
+        assert_eq!(protocol.light(&LightOption::Off), hex(POWER_OFF));
+    }
+
+    #[test]
+    fn encodes_color() {
+        let protocol = Govee::default();
+        assert_eq!(protocol.color(&ColorOption::RGB(255, 0, 0)), hex(COLOR_RED));
+        assert_eq!(
+            protocol.color(&ColorOption::RGB(0, 0, 255)),
+            hex(COLOR_BLUE)
+        );
+    }
+
+    #[test]
+    fn encodes_brightness() {
+        let protocol = Govee::default();
+        assert_eq!(
+            protocol.brightness(&BrightnessOption::Level(Level::FULL)),
+            hex(BRIGHTNESS_FULL)
+        );
+        assert_eq!(
+            protocol.brightness(&BrightnessOption::Level(Level::OFF)),
+            hex(BRIGHTNESS_OFF)
+        );
+    }
+
+    #[test]
+    fn encodes_white_temperature() {
+        // white mode: 0x33 0x05 0x02, white flag, then the rgb of the temperature
+        let white = Govee::default().white(Kelvin::saturating(2000));
+        assert_eq!(white.len(), PACKET_LEN);
+        assert_eq!(&white[..7], &hex("330502ffffff01")[..]);
+        assert_eq!(
+            white[PACKET_LEN - 1],
+            white[..PACKET_LEN - 1]
+                .iter()
+                .fold(0, |xor, byte| xor ^ byte)
+        );
+        // clamped to the supported range
+        assert_eq!(Govee::default().white(Kelvin::saturating(1000)), white);
+    }
+
+    #[test]
+    fn encodes_scene() {
+        assert_eq!(Govee::default().scene(0x0009), hex(SCENE_CANDLELIGHT));
+    }
+
+    #[test]
+    fn encodes_keep_alive() {
+        assert_eq!(Govee::default().keep_alive_packet(), hex(KEEP_ALIVE));
+    }
+}
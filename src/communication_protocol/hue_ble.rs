@@ -0,0 +1,199 @@
+use uuid::Uuid;
+
+#[cfg(feature = "temperature")]
+use crate::capability::temperature::TemperatureOption;
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::ColorOption,
+        hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
+        light::LightOption,
+    },
+    color::Xy,
+    communication_protocol::{CommandKind, Protocol},
+    device::{CharRole, CharTarget},
+    units::{Kelvin, Level},
+};
+
+// color temperature range in mired (6500K..=2000K)
+const MIRED_MIN: u16 = 153;
+const MIRED_MAX: u16 = 500;
+
+// brightness range, 0 is not accepted by the bulb
+const BRIGHTNESS_MIN: u8 = 0x01;
+const BRIGHTNESS_MAX: u8 = 0xFE;
+
+/// Protocol spoken by _Philips Hue_ Bluetooth bulbs.
+///
+/// Every command targets its own characteristic (see `HueBle::characteristics`),
+/// use it with a `HueDevice`. Colors are sent as _CIE xy_ coordinates.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::brightness::BrightnessOption;
+/// use ble_ledly::capability::light::LightOption;
+/// use ble_ledly::communication_protocol::{HueBle, Protocol};
+/// use ble_ledly::units::{Kelvin, Level};
+///
+/// let protocol = HueBle::default();
+///
+/// assert_eq!(protocol.light(&LightOption::On), vec![0x01]);
+/// assert_eq!(protocol.brightness(&BrightnessOption::Level(Level::FULL)), vec![0xFE]);
+/// assert_eq!(protocol.temperature(Kelvin::saturating(2700)), vec![0x72, 0x01]);
+/// ```
+#[derive(Default, Debug)]
+pub struct HueBle {}
+
+impl HueBle {
+    /// UUID of the light control service
+    pub const SERVICE_UUID: u128 = 0x932c32bd_0000_47a2_835a_a8d455b859dd;
+
+    /// Characteristics of the light control service
+    /// and the role each one is cached under.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::HueBle;
+    /// use ble_ledly::device::CharRole;
+    ///
+    /// let (role, uuid) = &HueBle::characteristics()[0];
+    /// assert_eq!(*role, CharRole::Power);
+    /// assert_eq!(uuid.to_string(), "932c32bd-0002-47a2-835a-a8d455b859dd");
+    /// ```
+    pub fn characteristics() -> Vec<(CharRole, Uuid)> {
+        [
+            (CharRole::Power, 0x0002u128),
+            (CharRole::Brightness, 0x0003),
+            (CharRole::Temperature, 0x0004),
+            (CharRole::Color, 0x0005),
+        ]
+        .into_iter()
+        .map(|(role, id)| (role, Uuid::from_u128(HueBle::SERVICE_UUID | (id << 80))))
+        .collect()
+    }
+
+    /// White light at the given temperature, clamped
+    /// to the supported `2000..=6500` Kelvin; to be written
+    /// to the `CharRole::Temperature` characteristic.
+    pub fn temperature(&self, kelvin: Kelvin) -> Vec<u8> {
+        let mired = (1_000_000 / kelvin.get() as u32) as u16;
+        mired.clamp(MIRED_MIN, MIRED_MAX).to_le_bytes().to_vec()
+    }
+
+    /// Decodes the value read from the power characteristic
+    pub fn decode_power(payload: &[u8]) -> Option<bool> {
+        payload.first().map(|on| *on != 0x00)
+    }
+
+    /// Decodes the value read from the brightness characteristic
+    pub fn decode_brightness(payload: &[u8]) -> Option<Level> {
+        payload.first().map(|level| {
+            Level::saturating(
+                (level.clamp(&BRIGHTNESS_MIN, &BRIGHTNESS_MAX) - BRIGHTNESS_MIN) as f32
+                    / (BRIGHTNESS_MAX - BRIGHTNESS_MIN) as f32,
+            )
+        })
+    }
+
+    /// Decodes the value read from the temperature characteristic
+    pub fn decode_temperature(payload: &[u8]) -> Option<Kelvin> {
+        let mired = u16::from_le_bytes([*payload.first()?, *payload.get(1)?]);
+        Some(Kelvin::saturating(
+            (1_000_000 / mired.max(1) as u32).min(u16::MAX as u32) as u16,
+        ))
+    }
+
+    /// Decodes the value read from the color characteristic into _CIE xy_
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::communication_protocol::{HueBle, Protocol};
+    ///
+    /// let payload = HueBle::default().color(&ColorOption::RGB(255, 0, 0));
+    /// let (x, y) = HueBle::decode_xy(&payload).unwrap();
+    ///
+    /// assert!((x - 0.7006).abs() < 0.001);
+    /// assert!((y - 0.2993).abs() < 0.001);
+    /// ```
+    pub fn decode_xy(payload: &[u8]) -> Option<(f32, f32)> {
+        let x = u16::from_le_bytes([*payload.first()?, *payload.get(1)?]);
+        let y = u16::from_le_bytes([*payload.get(2)?, *payload.get(3)?]);
+        Some((x as f32 / u16::MAX as f32, y as f32 / u16::MAX as f32))
+    }
+
+    fn _brightness(level: Level) -> u8 {
+        BRIGHTNESS_MIN + (level.get() * (BRIGHTNESS_MAX - BRIGHTNESS_MIN) as f32).round() as u8
+    }
+}
+
+impl Protocol for HueBle {
+    fn target(&self, command: &CommandKind) -> CharTarget {
+        match command {
+            CommandKind::Light => CharTarget::Role(CharRole::Power),
+            CommandKind::Color => CharTarget::Role(CharRole::Color),
+            CommandKind::Brightness => CharTarget::Role(CharRole::Brightness),
+            CommandKind::Temperature => CharTarget::Role(CharRole::Temperature),
+            CommandKind::HWAnimate
+            | CommandKind::Pixels
+            | CommandKind::Music
+            | CommandKind::Query => CharTarget::Default,
+        }
+    }
+
+    // Light //
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => vec![0x01],
+            LightOption::Off => vec![0x00],
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let xy = Xy::from(*option);
+        let x = (xy.x * u16::MAX as f32).round() as u16;
+        let y = (xy.y * u16::MAX as f32).round() as u16;
+        [x.to_le_bytes(), y.to_le_bytes()].concat()
+    }
+
+    fn native_brightness(&self) -> bool {
+        true
+    }
+
+    // The chromaticity does not depend on the level, so both options
+    // only drive the brightness characteristic: the color of
+    // `LevelWithColor` is written to its own characteristic by the
+    // `Brightness` capability, as a `CommandKind::Color`.
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            BrightnessOption::Level(level) | BrightnessOption::LevelWithColor(level, _) => {
+                vec![HueBle::_brightness(*level)]
+            }
+        }
+    }
+    // the temperature has its own characteristic, without the brightness:
+    // fall back to the color to keep the level
+    #[cfg(feature = "temperature")]
+    fn white_temperature(&self, option: &TemperatureOption) -> Option<Vec<u8>> {
+        match option {
+            TemperatureOption::Kelvin(kelvin) => Some(self.temperature(*kelvin)),
+            TemperatureOption::KelvinWithBrightness(_, _) => None,
+        }
+    }
+
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    fn hw_animate(&self, _option: &HWAnimateOption) -> Vec<u8> {
+        // none listed by `hw_animations`, never written
+        Vec::new()
+    }
+
+    fn _animation_speed(_setting: &HWAnimationSpeedSetting) -> u8 {
+        0x00
+    }
+
+    fn _static_color(_color: &HWStaticColorOption) -> u8 {
+        0x00
+    }
+}
@@ -1,26 +1,178 @@
+#[cfg(feature = "music")]
+use crate::capability::music::MusicOption;
+#[cfg(feature = "temperature")]
+use crate::capability::temperature::TemperatureOption;
 use crate::capability::{
     brightness::BrightnessOption,
-    color::ColorOption,
+    color::{ChannelLayout, ColorOption},
     hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
     light::LightOption,
 };
+use crate::device::{CharTarget, Device};
+use crate::error::BluetoothError;
+use async_trait::async_trait;
+use std::time::Duration;
 
+#[cfg(feature = "declarative")]
+pub mod declarative;
+pub mod elk_bledom;
+pub mod framing;
 pub mod generic_rgb;
+pub mod govee;
+pub mod hue_ble;
+#[cfg(feature = "pixels")]
+pub mod sp110e;
+#[cfg(feature = "tuya")]
+pub mod tuya_ble;
+pub mod yeelight;
+pub mod zengge;
 
 //----------//
 // Re-export//
 //----------//
 ////////////////////////////////////////
+#[cfg(feature = "declarative")]
+pub use self::declarative::DeclarativeProtocol;
+pub use self::elk_bledom::ElkBledom;
 pub use self::generic_rgb::GenericRGB;
+pub use self::govee::Govee;
+pub use self::hue_ble::HueBle;
+#[cfg(feature = "pixels")]
+pub use self::sp110e::Sp110e;
+#[cfg(feature = "tuya")]
+pub use self::tuya_ble::TuyaBle;
+pub use self::yeelight::Yeelight;
+pub use self::zengge::Zengge;
 ////////////////////////////////////////
 
+/// Kind of command encoded by a `Protocol`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandKind {
+    Light,
+    Color,
+    Brightness,
+    HWAnimate,
+    Pixels,
+    Temperature,
+    Music,
+    /// State query, see `StateProtocol::query_state`
+    Query,
+}
+
 pub trait Protocol {
     fn light(&self, option: &LightOption) -> Vec<u8>;
     fn color(&self, option: &ColorOption) -> Vec<u8>;
     fn brightness(&self, option: &BrightnessOption) -> Vec<u8>;
     fn hw_animate(&self, option: &HWAnimateOption) -> Vec<u8>;
 
+    /// Color channels of the controller; colors are converted to this
+    /// layout before `color` and `brightness`, `RGB` by default.
+    fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::RGB
+    }
+
+    /// Whether `brightness` encodes `BrightnessOption::Level` natively;
+    /// the `Brightness` capability scales the last color otherwise.
+    fn native_brightness(&self) -> bool {
+        false
+    }
+
+    /// Animations encoded by `hw_animate` at the given speed, so that
+    /// user interfaces can list them; empty if the protocol has none.
+    /// The `HWAnimate` capability rejects the other options as unsupported.
+    fn hw_animations<'e>(&self, _speed: &'e HWAnimationSpeedSetting) -> Vec<HWAnimateOption<'e>> {
+        Vec::new()
+    }
+
+    /// Frames starting an animation, written in order by the `HWAnimate`
+    /// capability: the `hw_animate` frame by default. Controllers setting
+    /// the speed with a separate command add its frame after it.
+    fn hw_animate_frames(&self, option: &HWAnimateOption) -> Vec<Vec<u8>> {
+        vec![self.hw_animate(option)]
+    }
+
+    /// Native color temperature (CCT) command, `None` if the protocol has
+    /// none: the `Temperature` capability then falls back to `color`.
+    #[cfg(feature = "temperature")]
+    fn white_temperature(&self, _option: &TemperatureOption) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Frames enabling or disabling the microphone mode of the controller,
+    /// written in order; `None` if the protocol has none: the `HWMusic`
+    /// capability then reports it as unsupported.
+    #[cfg(feature = "music")]
+    fn music(&self, _option: &MusicOption) -> Option<Vec<Vec<u8>>> {
+        None
+    }
+
+    /// Whether the protocol can encode commands, e.g. once the session
+    /// of its `Handshake` is established; `Write::push_command` fails
+    /// with the error otherwise, nothing is written.
+    fn ready(&self) -> Result<(), BluetoothError> {
+        Ok(())
+    }
+
+    /// Characteristic the frames of a command are written to.
+    /// Protocols driving multi-characteristic devices override it,
+    /// all commands go to the default write characteristic otherwise.
+    fn target(&self, _command: &CommandKind) -> CharTarget {
+        CharTarget::Default
+    }
+
+    /// Splits a frame into the packets written to the device, each at most
+    /// `mtu` bytes long. Protocols with their own fragmentation scheme
+    /// override it, frames are cut into consecutive chunks otherwise.
+    fn packets(&self, frame: &[u8], mtu: usize) -> Vec<Vec<u8>> {
+        frame.chunks(mtu.max(1)).map(<[u8]>::to_vec).collect()
+    }
+
+    /// Approximate period of one animation cycle at the given speed, as
+    /// encoded by `hw_animate`; `None` if the protocol does not know it.
+    fn animation_period(&self, _speed: &HWAnimationSpeedSetting) -> Option<Duration> {
+        None
+    }
+
     // animate hwspecific helpers //
     fn _animation_speed(setting: &HWAnimationSpeedSetting) -> u8;
     fn _static_color(color: &HWStaticColorOption) -> u8;
 }
+
+/// Protocols requiring an exchange with the device (pairing, session keys)
+/// before it accepts commands; run it once connected, see `Controller::handshake`.
+#[async_trait]
+pub trait Handshake {
+    async fn handshake<D: Device + std::marker::Send + std::marker::Sync>(
+        &self,
+        device: &D,
+    ) -> Result<(), BluetoothError>;
+}
+
+/// State reported by a device, see `StateProtocol`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceState {
+    pub light: LightOption,
+    // protocol-specific mode code
+    pub mode: u8,
+    // protocol-specific speed of the running mode
+    pub speed: u8,
+    pub color: ColorOption,
+    pub white: u8,
+}
+
+/// Protocols able to read back the state of the device, answered
+/// with a notification on the read characteristic; see `QueryState`.
+pub trait StateProtocol: Protocol {
+    fn query_state(&self) -> Vec<u8>;
+    /// Decodes a notification, `None` if it is not a state report
+    fn decode_state(&self, payload: &[u8]) -> Option<DeviceState>;
+}
+
+// Decodes the hex packets of the protocol tests
+#[cfg(test)]
+pub(crate) fn hex(packet: &str) -> Vec<u8> {
+    (0..packet.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&packet[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -0,0 +1,225 @@
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::ColorOption,
+        hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
+        light::LightOption,
+        pixels::{PixelProtocol, PixelsOption},
+    },
+    communication_protocol::Protocol,
+    device::ChannelOrder,
+    error::BluetoothError,
+    units::Level,
+};
+
+// commands are 3 parameters followed by the command byte, except
+// the pixel ones (see `PixelProtocol for Sp110e`): a segment has 7
+// parameters, a frame starts with its command byte
+const POWER_ON: u8 = 0xAA;
+const POWER_OFF: u8 = 0xAB;
+const SPEED: u8 = 0x03;
+const IC_MODEL: u8 = 0x1C;
+const COLOR: u8 = 0x1E;
+const BRIGHTNESS: u8 = 0x2A;
+const MODE: u8 = 0x2C;
+const PIXEL_COUNT: u8 = 0x2D;
+const FRAME: u8 = 0x2E;
+const SEGMENT: u8 = 0x2F;
+const COLOR_ORDER: u8 = 0x3C;
+
+/// Protocol spoken by _SP110E_ / _SP6xxE_ addressable pixel controllers
+/// (WS2812 and similar strips).
+///
+/// The controller has to be configured with the strip `Sp110eIc` model,
+/// `ChannelOrder` and pixel count before per-pixel commands
+/// (see `PixelProtocol`) address the right pixels.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::color::ColorOption;
+/// use ble_ledly::capability::pixels::{PixelProtocol, PixelsOption};
+/// use ble_ledly::communication_protocol::sp110e::{Sp110e, Sp110eIc};
+/// use ble_ledly::communication_protocol::Protocol;
+///
+/// let protocol = Sp110e::default();
+///
+/// assert_eq!(protocol.ic_model(&Sp110eIc::WS2811), vec![0x03, 0x00, 0x00, 0x1C]);
+/// assert_eq!(protocol.pixel_count(300), vec![0x01, 0x2C, 0x00, 0x2D]);
+/// assert_eq!(
+///     protocol.color(&ColorOption::RGB(255, 128, 0)),
+///     vec![0xFF, 0x80, 0x00, 0x1E]
+/// );
+/// assert_eq!(
+///     protocol.pixels(&PixelsOption::Segment(10..20, &ColorOption::RGB(0, 0, 255))).unwrap(),
+///     vec![0x00, 0x0A, 0x00, 0x14, 0x00, 0x00, 0xFF, 0x2F]
+/// );
+///
+/// // segments are not empty, frames carry at most 65535 pixels
+/// let blue = ColorOption::RGB(0, 0, 255);
+/// assert!(protocol.pixels(&PixelsOption::Segment(20..20, &blue)).is_err());
+/// let pixels = vec![ColorOption::RGB(0, 0, 0); 65536];
+/// assert!(protocol.pixels(&PixelsOption::Frame(&pixels)).is_err());
+/// ```
+#[derive(Default)]
+pub struct Sp110e {}
+
+impl Sp110e {
+    /// 16-bit UUID of the write characteristic
+    /// exposed by _SP110E_ controllers.
+    pub const WRITE_CHAR_UUID16: u16 = 0xFFE1;
+
+    /// Configures the driver IC of the strip
+    pub fn ic_model(&self, ic: &Sp110eIc) -> Vec<u8> {
+        vec![*ic as u8, 0x00, 0x00, IC_MODEL]
+    }
+
+    /// Configures the channel order of the strip
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::Sp110e;
+    /// use ble_ledly::device::ChannelOrder;
+    ///
+    /// assert_eq!(
+    ///     Sp110e::default().color_order(&ChannelOrder::GRB),
+    ///     vec![0x02, 0x00, 0x00, 0x3C]
+    /// );
+    /// ```
+    pub fn color_order(&self, order: &ChannelOrder) -> Vec<u8> {
+        let code = match order {
+            ChannelOrder::RGB => 0x00,
+            ChannelOrder::RBG => 0x01,
+            ChannelOrder::GRB => 0x02,
+            ChannelOrder::GBR => 0x03,
+            ChannelOrder::BRG => 0x04,
+            ChannelOrder::BGR => 0x05,
+        };
+        vec![code, 0x00, 0x00, COLOR_ORDER]
+    }
+
+    /// Configures the number of pixels of the strip
+    pub fn pixel_count(&self, count: u16) -> Vec<u8> {
+        let [high, low] = count.to_be_bytes();
+        vec![high, low, 0x00, PIXEL_COUNT]
+    }
+
+    /// Starts one of the built-in patterns (`1..=120`)
+    pub fn mode(&self, pattern: u8) -> Vec<u8> {
+        vec![pattern, 0x00, 0x00, MODE]
+    }
+
+    /// Sets the speed of the running pattern, from `Level::OFF` (slowest)
+    /// to `Level::FULL` (fastest).
+    pub fn speed(&self, speed: Level) -> Vec<u8> {
+        vec![Sp110e::_level(speed), 0x00, 0x00, SPEED]
+    }
+
+    fn _level(level: Level) -> u8 {
+        (level.get() * 255.0).round() as u8
+    }
+}
+
+/// Driver ICs supported by _SP110E_ controllers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sp110eIc {
+    SM16703 = 0x00,
+    TM1804 = 0x01,
+    UCS1903 = 0x02,
+    WS2811 = 0x03,
+    WS2801 = 0x04,
+    SK6812 = 0x05,
+    LPD6803 = 0x06,
+    LPD8806 = 0x07,
+    APA102 = 0x08,
+    APA105 = 0x09,
+    DMX512 = 0x0A,
+    TM1914 = 0x0B,
+    TM1913 = 0x0C,
+    P9813 = 0x0D,
+    INK1003 = 0x0E,
+    P943S = 0x0F,
+    P9411 = 0x10,
+    P9413 = 0x11,
+    TX1812 = 0x12,
+    TX1813 = 0x13,
+    GS8206 = 0x14,
+    GS8208 = 0x15,
+    SK9822 = 0x16,
+}
+
+impl Protocol for Sp110e {
+    // Light //
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => vec![0x00, 0x00, 0x00, POWER_ON],
+            LightOption::Off => vec![0x00, 0x00, 0x00, POWER_OFF],
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let (r, g, b) = option.rgb();
+        vec![r, g, b, COLOR]
+    }
+
+    fn native_brightness(&self) -> bool {
+        true
+    }
+
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            BrightnessOption::Level(level) => {
+                vec![Sp110e::_level(*level), 0x00, 0x00, BRIGHTNESS]
+            }
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
+        }
+    }
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    // built-in patterns are not grouped by color,
+    // use `Sp110e::mode` and `Sp110e::speed` instead.
+    fn hw_animate(&self, _option: &HWAnimateOption) -> Vec<u8> {
+        // none listed by `hw_animations`, never written
+        Vec::new()
+    }
+
+    fn _animation_speed(_setting: &HWAnimationSpeedSetting) -> u8 {
+        0x00
+    }
+
+    fn _static_color(_color: &HWStaticColorOption) -> u8 {
+        0x00
+    }
+}
+
+impl PixelProtocol for Sp110e {
+    // segment: start (u16 BE), end (u16 BE, exclusive), r, g, b, command
+    // frame: command, pixel count (u16 BE), then r, g, b for every pixel
+    fn pixels(&self, option: &PixelsOption) -> Result<Vec<u8>, BluetoothError> {
+        Ok(match option {
+            PixelsOption::Segment(range, color) => {
+                if range.start >= range.end {
+                    return Err(BluetoothError::EmptyPixelRange(range.start, range.end));
+                }
+                let (r, g, b) = color.rgb();
+                let mut frame = Vec::with_capacity(8);
+                frame.extend_from_slice(&range.start.to_be_bytes());
+                frame.extend_from_slice(&range.end.to_be_bytes());
+                frame.extend_from_slice(&[r, g, b, SEGMENT]);
+                frame
+            }
+            PixelsOption::Frame(pixels) => {
+                let mut frame = Vec::with_capacity(3 + pixels.len() * 3);
+                frame.push(FRAME);
+                let count = u16::try_from(pixels.len())
+                    .map_err(|_| BluetoothError::TooManyPixels(pixels.len(), u16::MAX as usize))?;
+                frame.extend_from_slice(&count.to_be_bytes());
+                for pixel in pixels.iter() {
+                    let (r, g, b) = pixel.rgb();
+                    frame.extend_from_slice(&[r, g, b]);
+                }
+                frame
+            }
+        })
+    }
+}
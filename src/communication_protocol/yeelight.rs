@@ -0,0 +1,334 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+use tokio::time;
+
+#[cfg(feature = "temperature")]
+use crate::capability::temperature::TemperatureOption;
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::ColorOption,
+        hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
+        light::LightOption,
+    },
+    communication_protocol::{DeviceState, Handshake, Protocol, StateProtocol},
+    device::{Device, Notify, Write},
+    error::BluetoothError,
+    units::{Kelvin, Level},
+};
+
+// frames: 0x43, command, payload, zero padding
+const FRAME_LEN: usize = 18;
+const FRAME_HEADER: u8 = 0x43;
+
+const POWER: u8 = 0x40;
+const COLOR: u8 = 0x41;
+const BRIGHTNESS: u8 = 0x42;
+const TEMPERATURE: u8 = 0x43;
+const STATE_REQUEST: u8 = 0x44;
+const STATE_RESPONSE: u8 = 0x45;
+const FLOW_STEP: u8 = 0x4A;
+const FLOW_START: u8 = 0x4B;
+const PAIRING_RESPONSE: u8 = 0x63;
+const PAIR: u8 = 0x67;
+
+const PAIR_KEY: [u8; 4] = [0xDE, 0xAD, 0xBE, 0xBF];
+
+const KELVIN_MIN: u16 = 1700;
+const KELVIN_MAX: u16 = 6500;
+
+// flow steps are numbered on one byte, and last a number of ticks
+const FLOW_STEPS_MAX: usize = u8::MAX as usize;
+const FLOW_TICK: Duration = Duration::from_millis(100);
+
+/// Protocol spoken by _Yeelight_ BLE lamps (bedside lamp, candela).
+///
+/// The lamp ignores every command until paired: run the `Handshake`
+/// (see `Controller::handshake`) with the write characteristic set to
+/// `Yeelight::WRITE_CHAR_UUID` and the read one to `Yeelight::NOTIFY_CHAR_UUID`,
+/// then press the button on the lamp when it starts blinking.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::light::LightOption;
+/// use ble_ledly::communication_protocol::{Protocol, Yeelight};
+///
+/// let mut on = vec![0x43, 0x40, 0x01];
+/// on.resize(18, 0x00);
+/// assert_eq!(Yeelight::default().light(&LightOption::On), on);
+/// ```
+#[derive(Default)]
+pub struct Yeelight {}
+
+/// Outcome of a pairing request, as notified by the lamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum YeelightPairing {
+    /// Waiting for the button on the lamp to be pressed
+    WaitingConfirmation,
+    Paired,
+    Failed,
+}
+
+/// Step of a color flow; the duration has a resolution of 100 ms,
+/// and is at least 100 ms and at most `u16::MAX` times 100 ms.
+pub struct YeelightFlowStep {
+    pub color: ColorOption,
+    pub brightness: Level,
+    pub duration: Duration,
+}
+
+impl Yeelight {
+    /// 128-bit UUID of the control (write) characteristic
+    pub const WRITE_CHAR_UUID: u128 = 0xaa7d3f34_2d4f_41e0_807f_52fbf8cf7443;
+
+    /// 128-bit UUID of the characteristic notifying pairing and state reports
+    pub const NOTIFY_CHAR_UUID: u128 = 0x8f65073d_9f57_4aaa_afea_397d19d5bbeb;
+
+    /// Time left to the user to confirm the pairing on the lamp
+    pub const PAIRING_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Modes reported in `DeviceState::mode`
+    pub const MODE_COLOR: u8 = 0x01;
+    pub const MODE_WHITE: u8 = 0x02;
+    pub const MODE_FLOW: u8 = 0x03;
+
+    /// White light at the given temperature, clamped to the supported
+    /// `1700..=6500` Kelvin, at the given brightness.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::communication_protocol::Yeelight;
+    /// use ble_ledly::units::{Kelvin, Level};
+    ///
+    /// let frame = Yeelight::default().temperature(Kelvin::saturating(2700), Level::FULL);
+    /// assert_eq!(&frame[..5], &[0x43, 0x43, 0x0A, 0x8C, 0x64]);
+    /// ```
+    pub fn temperature(&self, kelvin: Kelvin, brightness: Level) -> Vec<u8> {
+        let [high, low] = kelvin.get().clamp(KELVIN_MIN, KELVIN_MAX).to_be_bytes();
+        Yeelight::_frame(TEMPERATURE, &[high, low, Yeelight::_level(brightness)])
+    }
+
+    /// Frames programming a color flow, to be written in order (see
+    /// `Write::push_packets`); the flow loops over the steps until
+    /// another command is sent. Fails with more than 255 steps.
+    ///
+    /// ## Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::communication_protocol::yeelight::{Yeelight, YeelightFlowStep};
+    /// use ble_ledly::units::Level;
+    ///
+    /// let step = YeelightFlowStep {
+    ///     color: ColorOption::RGB(255, 0, 0),
+    ///     brightness: Level::FULL,
+    ///     duration: Duration::from_millis(1500),
+    /// };
+    /// let frames = Yeelight::default().flow(&[step]).unwrap();
+    ///
+    /// // step 0, red, full brightness, 15 ticks of 100 ms
+    /// assert_eq!(&frames[0][..9], &[0x43, 0x4A, 0x00, 0xFF, 0x00, 0x00, 0x64, 0x00, 0x0F]);
+    /// assert_eq!(&frames[1][..3], &[0x43, 0x4B, 0x01]);
+    ///
+    /// let steps: Vec<_> = (0..256)
+    ///     .map(|_| YeelightFlowStep {
+    ///         color: ColorOption::RGB(0, 0, 255),
+    ///         brightness: Level::FULL,
+    ///         duration: Duration::from_secs(1),
+    ///     })
+    ///     .collect();
+    /// assert!(Yeelight::default().flow(&steps).is_err());
+    /// ```
+    pub fn flow(&self, steps: &[YeelightFlowStep]) -> Result<Vec<Vec<u8>>, BluetoothError> {
+        if steps.len() > FLOW_STEPS_MAX {
+            return Err(BluetoothError::TooManySteps(steps.len(), FLOW_STEPS_MAX));
+        }
+        let mut frames = steps
+            .iter()
+            .zip(0u8..)
+            .map(|(step, index)| {
+                let (r, g, b) = step.color.rgb();
+                let [high, low] = Yeelight::_ticks(step.duration).to_be_bytes();
+                Yeelight::_frame(
+                    FLOW_STEP,
+                    &[index, r, g, b, Yeelight::_level(step.brightness), high, low],
+                )
+            })
+            .collect::<Vec<Vec<u8>>>();
+        frames.push(Yeelight::_frame(FLOW_START, &[steps.len() as u8]));
+        Ok(frames)
+    }
+
+    /// Decodes a pairing notification, `None` if the
+    /// payload is not a pairing response.
+    pub fn decode_pairing(payload: &[u8]) -> Option<YeelightPairing> {
+        match payload {
+            [FRAME_HEADER, PAIRING_RESPONSE, status, ..] => Some(match status {
+                0x01 => YeelightPairing::WaitingConfirmation,
+                // 0x04: already paired with this client
+                0x02 | 0x04 => YeelightPairing::Paired,
+                _ => YeelightPairing::Failed,
+            }),
+            _ => None,
+        }
+    }
+
+    fn _frame(command: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![FRAME_HEADER, command];
+        frame.extend_from_slice(payload);
+        frame.resize(FRAME_LEN, 0x00);
+        frame
+    }
+
+    // rounded to the nearest tick, at least one
+    fn _ticks(duration: Duration) -> u16 {
+        let ticks = (duration.as_millis() + FLOW_TICK.as_millis() / 2) / FLOW_TICK.as_millis();
+        ticks.clamp(1, u16::MAX as u128) as u16
+    }
+
+    // brightness levels range 1..=100
+    fn _level(level: Level) -> u8 {
+        (level.get() * 100.0).round().max(1.0) as u8
+    }
+}
+
+impl Protocol for Yeelight {
+    // Light //
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => Yeelight::_frame(POWER, &[0x01]),
+            LightOption::Off => Yeelight::_frame(POWER, &[0x02]),
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let (r, g, b) = option.rgb();
+        Yeelight::_frame(COLOR, &[r, g, b, 0x00])
+    }
+
+    fn native_brightness(&self) -> bool {
+        true
+    }
+
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            BrightnessOption::Level(level) => {
+                Yeelight::_frame(BRIGHTNESS, &[Yeelight::_level(*level)])
+            }
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
+        }
+    }
+    // without brightness, the temperature is set at full brightness
+    #[cfg(feature = "temperature")]
+    fn white_temperature(&self, option: &TemperatureOption) -> Option<Vec<u8>> {
+        Some(match option {
+            TemperatureOption::Kelvin(kelvin) => self.temperature(*kelvin, Level::FULL),
+            TemperatureOption::KelvinWithBrightness(kelvin, level) => {
+                self.temperature(*kelvin, *level)
+            }
+        })
+    }
+
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    // animations are programmed as multi-frame
+    // color flows, use `Yeelight::flow` instead.
+    fn hw_animate(&self, _option: &HWAnimateOption) -> Vec<u8> {
+        // none listed by `hw_animations`, never written
+        Vec::new()
+    }
+
+    fn _animation_speed(_setting: &HWAnimationSpeedSetting) -> u8 {
+        0x00
+    }
+
+    fn _static_color(_color: &HWStaticColorOption) -> u8 {
+        0x00
+    }
+}
+
+impl StateProtocol for Yeelight {
+    fn query_state(&self) -> Vec<u8> {
+        Yeelight::_frame(STATE_REQUEST, &[])
+    }
+
+    /// Decodes a state notification; the mode is one of `Yeelight::MODE_COLOR`,
+    /// `Yeelight::MODE_WHITE` and `Yeelight::MODE_FLOW`, and the speed is
+    /// always `0`.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::capability::light::LightOption;
+    /// use ble_ledly::communication_protocol::{StateProtocol, Yeelight};
+    ///
+    /// let report = [
+    ///     0x43, 0x45, 0x01, 0x01, 0xFF, 0x80, 0x00, 0x00, 0x32, 0x0A, 0x8C, 0x00, 0x00, 0x00,
+    ///     0x00, 0x00, 0x00, 0x00,
+    /// ];
+    /// let state = Yeelight::default().decode_state(&report).unwrap();
+    ///
+    /// assert_eq!(state.light, LightOption::On);
+    /// assert_eq!(state.mode, Yeelight::MODE_COLOR);
+    /// assert_eq!(state.color, ColorOption::RGB(0xFF, 0x80, 0x00));
+    /// ```
+    fn decode_state(&self, payload: &[u8]) -> Option<DeviceState> {
+        match payload {
+            [FRAME_HEADER, STATE_RESPONSE, power, mode, r, g, b, white, ..]
+                if (Yeelight::MODE_COLOR..=Yeelight::MODE_FLOW).contains(mode) =>
+            {
+                Some(DeviceState {
+                    light: match power {
+                        0x01 => LightOption::On,
+                        _ => LightOption::Off,
+                    },
+                    mode: *mode,
+                    speed: 0x00,
+                    color: ColorOption::RGB(*r, *g, *b),
+                    white: *white,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl Handshake for Yeelight {
+    // Sends the pairing request and waits for the user
+    // to confirm it by pressing the button on the lamp.
+    async fn handshake<D: Device + std::marker::Send + std::marker::Sync>(
+        &self,
+        device: &D,
+    ) -> Result<(), BluetoothError> {
+        let mut notifications = device.notifications().await?;
+        let paired = async {
+            device.push(&Yeelight::_frame(PAIR, &PAIR_KEY)).await?;
+            time::timeout(Yeelight::PAIRING_TIMEOUT, async {
+                while let Some(value) = notifications.next().await {
+                    match Yeelight::decode_pairing(&value) {
+                        Some(YeelightPairing::Paired) => return Ok(()),
+                        Some(YeelightPairing::Failed) => {
+                            return Err(BluetoothError::HandshakeFailed)
+                        }
+                        // waiting for the button, or not a pairing response
+                        _ => continue,
+                    }
+                }
+                Err(BluetoothError::HandshakeFailed)
+            })
+            .await
+            .map_err(|_| BluetoothError::Timeout)?
+        }
+        .await;
+
+        // subscribed for the pairing only, whatever the outcome
+        drop(notifications);
+        let unsubscribed = device.unsubscribe().await;
+        paired?;
+        unsubscribed
+    }
+}
@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+#[cfg(feature = "temperature")]
+use crate::capability::temperature::TemperatureOption;
+use crate::units::{Kelvin, Level};
+use crate::{
+    capability::{
+        brightness::BrightnessOption,
+        color::{ChannelLayout, ColorOption},
+        hw_animate::{HWAnimateOption, HWAnimationSpeedSetting, HWStaticColorOption},
+        light::LightOption,
+    },
+    communication_protocol::{
+        framing::{Checksum, Framing},
+        DeviceState, GenericRGB, Protocol, StateProtocol,
+    },
+};
+
+// outer header: 0x00, sequence, 0x80, 0x00, 0x00, len, len + 1, 0x0B
+const HEADER_LEN: usize = 8;
+const STATE_LEN: usize = 14;
+const STATE_RESPONSE: u8 = 0x81;
+
+/// Protocol spoken by _Zengge LEDnet_ BLE devices (_MagicHome_ app).
+///
+/// Every command is terminated by a _sum8_ checksum and wrapped in a header
+/// carrying a sequence number, which is incremented on every encoded command.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::color::{ChannelLayout, ColorOption};
+/// use ble_ledly::capability::light::LightOption;
+/// use ble_ledly::communication_protocol::{Protocol, Zengge};
+///
+/// let protocol = Zengge::default();
+///
+/// assert_eq!(
+///     protocol.light(&LightOption::On),
+///     vec![0x00, 0x00, 0x80, 0x00, 0x00, 0x04, 0x05, 0x0B, 0x71, 0x23, 0x0F, 0xA3]
+/// );
+/// // the sequence number advances
+/// assert_eq!(protocol.light(&LightOption::Off)[1], 0x01);
+///
+/// // warm and cold white channels are driven along the RGB ones
+/// let protocol = Zengge::with_layout(ChannelLayout::RGBWW);
+/// assert_eq!(
+///     &protocol.color(&ColorOption::RGBWW(255, 0, 0, 128, 0))[8..15],
+///     &[0x31, 0xFF, 0x00, 0x00, 0x80, 0x00, 0xFF]
+/// );
+/// ```
+pub struct Zengge {
+    framing: Framing,
+    layout: ChannelLayout,
+}
+
+impl Default for Zengge {
+    fn default() -> Self {
+        Self {
+            framing: Zengge::_framing(),
+            layout: ChannelLayout::default(),
+        }
+    }
+}
+
+impl Zengge {
+    /// Temperatures of the warm and cold white channels, mixed by `Temperature`
+    pub const WARM_WHITE: Kelvin = Kelvin::saturating(2700);
+    pub const COLD_WHITE: Kelvin = Kelvin::saturating(6500);
+
+    /// Device with the given color channels
+    pub fn with_layout(layout: ChannelLayout) -> Self {
+        Self {
+            layout,
+            ..Default::default()
+        }
+    }
+
+    /// Drives the white channels at the given levels.
+    pub fn white(&self, warm: Level, cold: Level) -> Vec<u8> {
+        self._wrap(&[
+            0x31,
+            0x00,
+            0x00,
+            0x00,
+            Zengge::_level(warm),
+            Zengge::_level(cold),
+            0x0F,
+            0x0F,
+        ])
+    }
+
+    /// Starts one of the built-in modes (`0x25..=0x38`, same table as `GenericRGB`).
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::hw_animate::HWAnimationSpeedSetting;
+    /// use ble_ledly::communication_protocol::Zengge;
+    ///
+    /// let frame = Zengge::default().mode(0x25, &HWAnimationSpeedSetting::Speed9);
+    /// assert_eq!(&frame[8..], &[0x61, 0x25, 0x01, 0x0F, 0x96]);
+    /// ```
+    pub fn mode(&self, mode: u8, speed: &HWAnimationSpeedSetting) -> Vec<u8> {
+        self._wrap(&[0x61, mode, Zengge::_animation_speed(speed), 0x0F])
+    }
+
+    // the checksum only covers the command, after the outer header
+    fn _framing() -> Framing {
+        Framing::new()
+            .header(&[0x00])
+            .sequence()
+            .checksum(Checksum::Sum8)
+            .checksum_from(HEADER_LEN)
+    }
+
+    // the end of the outer header holds the lengths of the command
+    fn _wrap(&self, command: &[u8]) -> Vec<u8> {
+        let len = command.len() as u8 + 1;
+        self.framing
+            .frame(&[&[0x80, 0x00, 0x00, len, len + 1, 0x0B], command].concat())
+    }
+
+    fn _level(level: Level) -> u8 {
+        (level.get() * 255.0).round() as u8
+    }
+}
+
+impl Protocol for Zengge {
+    // Light //
+    fn light(&self, option: &LightOption) -> Vec<u8> {
+        match option {
+            LightOption::On => self._wrap(&[0x71, 0x23, 0x0F]),
+            LightOption::Off => self._wrap(&[0x71, 0x24, 0x0F]),
+        }
+    }
+
+    fn color(&self, option: &ColorOption) -> Vec<u8> {
+        let (r, g, b, warm, cold) = match *option {
+            ColorOption::RGB(r, g, b) => (r, g, b, 0x00, 0x00),
+            ColorOption::RGBW(r, g, b, w) => (r, g, b, w, 0x00),
+            ColorOption::RGBWW(r, g, b, warm, cold) => (r, g, b, warm, cold),
+        };
+        // which of the color (0xF0) and white (0x0F) channels are written
+        let mask = match (r | g | b, warm | cold) {
+            (_, 0) => 0xF0,
+            (0, _) => 0x0F,
+            _ => 0xFF,
+        };
+        self._wrap(&[0x31, r, g, b, warm, cold, mask, 0x0F])
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        self.layout
+    }
+
+    // mixed on the white channels, when the device has both
+    #[cfg(feature = "temperature")]
+    fn white_temperature(&self, option: &TemperatureOption) -> Option<Vec<u8>> {
+        (self.layout == ChannelLayout::RGBWW).then(|| {
+            let (warm, cold) = option.white_channels(Zengge::WARM_WHITE, Zengge::COLD_WHITE);
+            self.white(warm, cold)
+        })
+    }
+
+    fn brightness(&self, option: &BrightnessOption) -> Vec<u8> {
+        match option {
+            // no brightness command, white at the given level
+            BrightnessOption::Level(level) => {
+                self.color(&ColorOption::RGB(255, 255, 255).scaled(*level))
+            }
+            BrightnessOption::LevelWithColor(level, color) => self.color(&color.scaled(*level)),
+        }
+    }
+    //-----------//
+    // HWAnimate //
+    //-----------//
+    fn hw_animate(&self, option: &HWAnimateOption) -> Vec<u8> {
+        self.mode(GenericRGB::_mode(option), option.speed())
+    }
+
+    fn animation_period(&self, speed: &HWAnimationSpeedSetting) -> Option<Duration> {
+        GenericRGB::default().animation_period(speed)
+    }
+
+    fn hw_animations<'e>(&self, speed: &'e HWAnimationSpeedSetting) -> Vec<HWAnimateOption<'e>> {
+        HWAnimateOption::all(speed)
+    }
+
+    // built-in modes share the Triones tables
+    fn _animation_speed(setting: &HWAnimationSpeedSetting) -> u8 {
+        GenericRGB::_animation_speed(setting)
+    }
+
+    fn _static_color(color: &HWStaticColorOption) -> u8 {
+        GenericRGB::_static_color(color)
+    }
+}
+
+impl StateProtocol for Zengge {
+    fn query_state(&self) -> Vec<u8> {
+        self._wrap(&[0x81, 0x8A, 0x8B])
+    }
+
+    /// Decodes a state notification, either wrapped or bare;
+    /// the white channels are reported along the RGB ones.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::{ChannelLayout, ColorOption};
+    /// use ble_ledly::capability::light::LightOption;
+    /// use ble_ledly::communication_protocol::{StateProtocol, Zengge};
+    ///
+    /// let report = [
+    ///     0x81, 0x04, 0x23, 0x61, 0x01, 0x10, 0xFF, 0x80, 0x00, 0x00, 0x05, 0x00, 0xF0, 0x8E,
+    /// ];
+    /// let state = Zengge::default().decode_state(&report).unwrap();
+    ///
+    /// assert_eq!(state.light, LightOption::On);
+    /// assert_eq!(state.mode, 0x61);
+    /// assert_eq!(state.speed, 0x10);
+    /// assert_eq!(state.color, ColorOption::RGB(0xFF, 0x80, 0x00));
+    ///
+    /// let state = Zengge::with_layout(ChannelLayout::RGBWW).decode_state(&report).unwrap();
+    /// assert_eq!(state.color, ColorOption::RGBWW(0xFF, 0x80, 0x00, 0x00, 0x00));
+    /// ```
+    fn decode_state(&self, payload: &[u8]) -> Option<DeviceState> {
+        let report = match payload.first() {
+            Some(&STATE_RESPONSE) => payload,
+            _ => payload.get(HEADER_LEN..)?,
+        };
+        let report = report.get(..STATE_LEN)?;
+        if report[0] != STATE_RESPONSE
+            || Checksum::Sum8.compute(&report[..STATE_LEN - 1]) != report[STATE_LEN - 1..]
+        {
+            return None;
+        }
+        // model, power, mode, _, speed, r, g, b, warm, version, cold
+        let (r, g, b, warm, cold) = (report[6], report[7], report[8], report[9], report[11]);
+        Some(DeviceState {
+            light: match report[2] {
+                0x23 => LightOption::On,
+                _ => LightOption::Off,
+            },
+            mode: report[3],
+            speed: report[5],
+            color: match self.layout {
+                ChannelLayout::RGB => ColorOption::RGB(r, g, b),
+                ChannelLayout::RGBW => ColorOption::RGBW(r, g, b, warm),
+                ChannelLayout::RGBWW => ColorOption::RGBWW(r, g, b, warm, cold),
+            },
+            white: warm,
+        })
+    }
+}
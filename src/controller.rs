@@ -1,4 +1,5 @@
-use crate::device::{CharKind, Device, UuidKind};
+use crate::communication_protocol::Handshake;
+use crate::device::{CharKind, CharRole, Device, UuidKind};
 use crate::error::BluetoothError;
 use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
 use btleplug::platform::{Adapter, Manager};
@@ -98,8 +99,27 @@ impl<D: Device> Controller<D> {
     ) -> Result<(), BluetoothError> {
         self.devices
             .iter_mut()
-            .map(|device| device.set_char(char_kind, uuid_kind))
-            .collect::<Result<(), BluetoothError>>()?;
+            .try_for_each(|device| device.set_char(char_kind, uuid_kind))?;
+        Ok(())
+    }
+
+    /// Caches the characteristic with the provided `UuidKind` under `role`
+    /// for all the devices; used by protocols routing their commands to
+    /// several characteristics.
+    ///
+    /// # Examples
+    ///
+    /// ```compile_fail
+    /// controller.set_all_role_char(CharRole::Power, &UuidKind::Uuid16(0xFFE1))?;
+    /// ````
+    pub fn set_all_role_char(
+        &mut self,
+        role: CharRole,
+        uuid_kind: &UuidKind,
+    ) -> Result<(), BluetoothError> {
+        self.devices
+            .iter_mut()
+            .try_for_each(|device| device.set_role_char(role.clone(), uuid_kind))?;
         Ok(())
     }
 
@@ -162,7 +182,7 @@ impl<D: Device> Controller<D> {
                 .unwrap_or(String::from("Unknown"));
 
             if name.contains(self.prefix.as_ref().unwrap_or(&"".to_string())) {
-                devices.push(D::new(&name, &name, Some(p), None, None));
+                devices.push(D::new(name, name, Some(p), None, None));
             }
         }
         Ok(devices)
@@ -235,4 +255,32 @@ impl<D: Device> Controller<D> {
         }
         Ok(())
     }
+
+    //-----------//
+    // Handshake //
+    //-----------//
+    /// Runs the `protocol` handshake (pairing, session setup) with all the
+    /// connected devices; required by protocols implementing `Handshake`
+    /// before any command is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```compile_fail
+    /// controller.connect().await?;
+    /// controller.set_all_char(&CharKind::Write, &UuidKind::Uuid128(Yeelight::WRITE_CHAR_UUID))?;
+    /// controller.set_all_char(&CharKind::Read, &UuidKind::Uuid128(Yeelight::NOTIFY_CHAR_UUID))?;
+    /// controller.handshake(&protocol).await?;
+    /// ```
+    pub async fn handshake<P: Handshake + std::marker::Sync>(
+        &self,
+        protocol: &P,
+    ) -> Result<(), BluetoothError>
+    where
+        D: std::marker::Send + std::marker::Sync,
+    {
+        for device in self.devices.iter() {
+            protocol.handshake(device).await?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,325 @@
+use crate::capability::color::ColorOption;
+use crate::units::Level;
+
+// binary search steps of each guided calibration question
+const GUIDE_STEPS: u8 = 7;
+// highest level proposed when looking for the minimum visible level
+const GUIDE_MIN_LEVEL_MAX: u8 = 64;
+
+/// Color calibration profile of a device, applied to every color before
+/// it is encoded by the protocol (see the `Color`, `Brightness`,
+/// `Temperature`, `Pixels` and `SWAnimate` capabilities).
+///
+/// Each channel `c` is mapped to `255 * (c / 255)^gamma * gain`, and
+/// raised to `min_level` when non-zero but below it; brightness levels
+/// scale the color before it is calibrated. The default
+/// profile leaves colors untouched. Use `CalibrationGuide` to
+/// build a profile from the answers of the user.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::color::ColorOption;
+/// use ble_ledly::device::Calibration;
+/// use ble_ledly::units::Level;
+///
+/// let calibration = Calibration {
+///     gamma: [2.2, 2.2, 2.2],
+///     // bluish white point
+///     gain: [1.0, 1.0, 0.8],
+///     min_level: 4,
+/// };
+///
+/// assert_eq!(calibration.color(&ColorOption::RGB(255, 128, 1)), ColorOption::RGB(255, 56, 4));
+/// assert_eq!(calibration.color(&ColorOption::RGB(0, 0, 255)), ColorOption::RGB(0, 0, 204));
+/// // dimmed colors stay visible
+/// let dimmed = ColorOption::RGB(255, 0, 0).scaled(Level::new(0.02).unwrap());
+/// assert_eq!(calibration.color(&dimmed), ColorOption::RGB(4, 0, 0));
+/// assert_eq!(
+///     Calibration::default().color(&ColorOption::RGB(255, 180, 0)),
+///     ColorOption::RGB(255, 180, 0)
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Exponent of each channel (red, green, blue)
+    pub gamma: [f32; 3],
+    /// Gain of each channel, from `0.0` to `1.0`, setting the white point
+    pub gain: [f32; 3],
+    /// Lowest native value at which the LEDs are visibly on
+    pub min_level: u8,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            gamma: [1.0; 3],
+            gain: [1.0; 3],
+            min_level: 0,
+        }
+    }
+}
+
+impl Calibration {
+    /// Calibrated color, to be encoded by the protocol; white
+    /// channels get the average gamma and no gain
+    pub fn color(&self, color: &ColorOption) -> ColorOption {
+        let gamma = self._mean_gamma();
+        let white = |value: u8| self._calibrate(value, gamma, 1.0);
+        let (r, g, b) = match *color {
+            ColorOption::RGB(r, g, b)
+            | ColorOption::RGBW(r, g, b, _)
+            | ColorOption::RGBWW(r, g, b, _, _) => (
+                self._calibrate(r, self.gamma[0], self.gain[0]),
+                self._calibrate(g, self.gamma[1], self.gain[1]),
+                self._calibrate(b, self.gamma[2], self.gain[2]),
+            ),
+        };
+        match *color {
+            ColorOption::RGB(..) => ColorOption::RGB(r, g, b),
+            ColorOption::RGBW(_, _, _, w) => ColorOption::RGBW(r, g, b, white(w)),
+            ColorOption::RGBWW(_, _, _, warm, cold) => {
+                ColorOption::RGBWW(r, g, b, white(warm), white(cold))
+            }
+        }
+    }
+
+    /// Calibrated brightness level, for protocols scaling the channels
+    /// linearly; the average gamma of the channels is applied.
+    pub fn level(&self, level: Level) -> Level {
+        Level::saturating(level.get().powf(self._mean_gamma()))
+    }
+
+    fn _mean_gamma(&self) -> f32 {
+        self.gamma.iter().sum::<f32>() / 3.0
+    }
+
+    fn _calibrate(&self, value: u8, gamma: f32, gain: f32) -> u8 {
+        if value == 0 {
+            return 0;
+        }
+        let calibrated = (value as f32 / 255.0).powf(gamma) * gain.clamp(0.0, 1.0) * 255.0;
+        (calibrated.round() as u8).max(self.min_level)
+    }
+}
+
+//-------//
+// Guide //
+//-------//
+/// Answer to a `CalibrationGuide` question, comparing
+/// the candidate color with what the question asks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Lower,
+    Match,
+    Higher,
+}
+
+/// Question asked by a `CalibrationGuide`: show `reference`, then
+/// `candidate`, and answer `prompt` with an `Answer`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalibrationQuestion {
+    pub reference: ColorOption,
+    pub candidate: ColorOption,
+    pub prompt: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GuideStage {
+    // gain of a channel, searched in 0.0..=1.0
+    WhiteBalance(usize),
+    // native value perceived at half the full brightness of a channel
+    Gamma(usize),
+    MinLevel,
+    Done,
+}
+
+/// Guided calibration, asking the user to compare colors shown on the
+/// device: white balance, then the gamma of each channel, then the
+/// minimum visible level. Every question is a binary search, answered
+/// `Match` to move on early.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::device::{Answer, CalibrationGuide};
+///
+/// let mut guide = CalibrationGuide::new();
+/// while let Some(question) = guide.question() {
+///     // display `question.reference`, then `question.candidate`,
+///     // and ask `question.prompt` to the user
+///     guide.answer(Answer::Match);
+/// }
+///
+/// let calibration = guide.calibration();
+/// assert_eq!(calibration.gain, [1.0, 1.0, 1.0]);
+/// assert_eq!(calibration.gamma, [1.0, 1.0, 1.0]);
+/// assert_eq!(calibration.min_level, 32);
+///
+/// // the first white shows too much red
+/// let mut guide = CalibrationGuide::new();
+/// guide.answer(Answer::Higher);
+/// assert_eq!(guide.question().unwrap().candidate.to_hex(), "#80ffff");
+/// guide.answer(Answer::Match);
+/// assert_eq!(guide.calibration().gain[0], 0.5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CalibrationGuide {
+    stage: GuideStage,
+    // bounds of the current binary search, and its steps left
+    low: f32,
+    high: f32,
+    candidate: f32,
+    steps: u8,
+    calibration: Calibration,
+}
+
+impl Default for CalibrationGuide {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CalibrationGuide {
+    pub fn new() -> Self {
+        let mut guide = Self {
+            stage: GuideStage::WhiteBalance(0),
+            low: 0.0,
+            high: 0.0,
+            candidate: 0.0,
+            steps: 0,
+            calibration: Calibration::default(),
+        };
+        guide._start(GuideStage::WhiteBalance(0));
+        guide
+    }
+
+    /// Current question, `None` once the calibration is complete
+    pub fn question(&self) -> Option<CalibrationQuestion> {
+        let channel_name = |index: usize| ["red", "green", "blue"][index];
+        let channel = |index: usize, value: u8| {
+            let mut channels = [0; 3];
+            channels[index] = value;
+            ColorOption::RGB(channels[0], channels[1], channels[2])
+        };
+        match self.stage {
+            GuideStage::WhiteBalance(index) => {
+                let mut gain = self.calibration.gain;
+                gain[index] = self.candidate;
+                let white = |gain: [f32; 3]| {
+                    let level = |g: f32| (g * 255.0).round() as u8;
+                    ColorOption::RGB(level(gain[0]), level(gain[1]), level(gain[2]))
+                };
+                Some(CalibrationQuestion {
+                    reference: white(self.calibration.gain),
+                    candidate: white(gain),
+                    prompt: format!(
+                        "Is there too little ({:?}), too much ({:?}) or the right amount ({:?}) of {} in this white?",
+                        Answer::Lower,
+                        Answer::Higher,
+                        Answer::Match,
+                        channel_name(index)
+                    ),
+                })
+            }
+            GuideStage::Gamma(index) => Some(CalibrationQuestion {
+                reference: channel(index, 255),
+                candidate: channel(index, self.candidate.round() as u8),
+                prompt: format!(
+                    "Compared to half the brightness of the full {}, is this dimmer ({:?}), brighter ({:?}) or the same ({:?})?",
+                    channel_name(index),
+                    Answer::Lower,
+                    Answer::Higher,
+                    Answer::Match
+                ),
+            }),
+            GuideStage::MinLevel => {
+                let level = self.candidate.round() as u8;
+                Some(CalibrationQuestion {
+                    reference: ColorOption::RGB(0, 0, 0),
+                    candidate: ColorOption::RGB(level, level, level),
+                    prompt: format!(
+                        "Are the LEDs off ({:?}), clearly on ({:?}) or barely visible ({:?})?",
+                        Answer::Lower,
+                        Answer::Higher,
+                        Answer::Match
+                    ),
+                })
+            }
+            GuideStage::Done => None,
+        }
+    }
+
+    /// Answers the current question
+    pub fn answer(&mut self, answer: Answer) {
+        if self.stage == GuideStage::Done {
+            return;
+        }
+        // every question is asked so that `Lower`
+        // calls for a higher candidate value
+        match answer {
+            Answer::Lower => self.low = self.candidate,
+            Answer::Higher => self.high = self.candidate,
+            Answer::Match => self.steps = 0,
+        }
+        if self.steps > 0 {
+            self.steps -= 1;
+            self.candidate = (self.low + self.high) / 2.0;
+        }
+        if self.steps == 0 {
+            self._finish();
+        }
+    }
+
+    /// Profile built from the answers so far
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    fn _start(&mut self, stage: GuideStage) {
+        let (low, high, candidate) = match stage {
+            GuideStage::WhiteBalance(_) => (0.0, 1.0, 1.0),
+            // a linear response looks half as bright at half the value
+            GuideStage::Gamma(_) => (0.0, 255.0, 127.5),
+            GuideStage::MinLevel => (
+                0.0,
+                GUIDE_MIN_LEVEL_MAX as f32,
+                GUIDE_MIN_LEVEL_MAX as f32 / 2.0,
+            ),
+            GuideStage::Done => (0.0, 0.0, 0.0),
+        };
+        self.stage = stage;
+        self.low = low;
+        self.high = high;
+        self.candidate = candidate;
+        self.steps = GUIDE_STEPS;
+    }
+
+    fn _finish(&mut self) {
+        let next = match self.stage {
+            GuideStage::WhiteBalance(index) => {
+                self.calibration.gain[index] = self.candidate;
+                if index < 2 {
+                    GuideStage::WhiteBalance(index + 1)
+                } else {
+                    GuideStage::Gamma(0)
+                }
+            }
+            GuideStage::Gamma(index) => {
+                // `candidate` out of 255 looks half as bright as 255
+                let value = self.candidate.clamp(1.0, 254.0) / 255.0;
+                self.calibration.gamma[index] = value.ln() / 0.5f32.ln();
+                if index < 2 {
+                    GuideStage::Gamma(index + 1)
+                } else {
+                    GuideStage::MinLevel
+                }
+            }
+            GuideStage::MinLevel => {
+                self.calibration.min_level = self.candidate.round() as u8;
+                GuideStage::Done
+            }
+            GuideStage::Done => GuideStage::Done,
+        };
+        self._start(next);
+    }
+}
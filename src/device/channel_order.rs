@@ -0,0 +1,216 @@
+use crate::capability::color::ColorOption;
+
+/// Primary color of an LED channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+}
+
+/// Wiring of a strip: the color shown by each channel of the
+/// controller, in order. `GRB` strips show green on the first channel.
+///
+/// Applied to every color written to a device (see `Device::channel_order`),
+/// after calibration; white channels are left untouched. For controllers
+/// with their own order setting, prefer it (e.g. `Sp110e::color_order`).
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::color::ColorOption;
+/// use ble_ledly::device::ChannelOrder;
+///
+/// assert_eq!(ChannelOrder::GRB.apply(&ColorOption::RGB(255, 0, 0)), ColorOption::RGB(0, 255, 0));
+/// assert_eq!(
+///     ChannelOrder::BRG.apply(&ColorOption::RGBW(1, 2, 3, 4)),
+///     ColorOption::RGBW(3, 1, 2, 4)
+/// );
+/// assert_eq!(ChannelOrder::RGB.apply(&ColorOption::RGB(1, 2, 3)), ColorOption::RGB(1, 2, 3));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelOrder {
+    #[default]
+    RGB,
+    RBG,
+    GRB,
+    GBR,
+    BRG,
+    BGR,
+}
+
+static ORDERS: [ChannelOrder; 6] = [
+    ChannelOrder::RGB,
+    ChannelOrder::RBG,
+    ChannelOrder::GRB,
+    ChannelOrder::GBR,
+    ChannelOrder::BRG,
+    ChannelOrder::BGR,
+];
+
+impl ChannelOrder {
+    pub fn all() -> &'static [ChannelOrder] {
+        &ORDERS
+    }
+
+    /// Colors shown by the channels of the controller, in order
+    pub fn channels(&self) -> [ColorChannel; 3] {
+        use ColorChannel::*;
+        match self {
+            ChannelOrder::RGB => [Red, Green, Blue],
+            ChannelOrder::RBG => [Red, Blue, Green],
+            ChannelOrder::GRB => [Green, Red, Blue],
+            ChannelOrder::GBR => [Green, Blue, Red],
+            ChannelOrder::BRG => [Blue, Red, Green],
+            ChannelOrder::BGR => [Blue, Green, Red],
+        }
+    }
+
+    /// Order whose channels show the given colors, `None`
+    /// if a color is repeated
+    pub fn from_channels(channels: [ColorChannel; 3]) -> Option<ChannelOrder> {
+        ORDERS
+            .iter()
+            .find(|order| order.channels() == channels)
+            .copied()
+    }
+
+    /// Order undoing this one, e.g. to read back
+    /// the colors reported by a device
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::device::ChannelOrder;
+    ///
+    /// let color = ColorOption::RGB(1, 2, 3);
+    /// for order in ChannelOrder::all() {
+    ///     assert_eq!(order.inverse().apply(&order.apply(&color)), color);
+    /// }
+    /// assert_eq!(ChannelOrder::BRG.inverse(), ChannelOrder::GBR);
+    /// ```
+    pub fn inverse(&self) -> ChannelOrder {
+        use ColorChannel::*;
+        let channels = self.channels();
+        // the color read on a channel is the one of its position
+        let source = |color: ColorChannel| match channels.iter().position(|c| *c == color) {
+            Some(0) => Red,
+            Some(1) => Green,
+            _ => Blue,
+        };
+        ChannelOrder::from_channels([source(Red), source(Green), source(Blue)]).unwrap_or_default()
+    }
+
+    /// Reorders the red, green and blue values so
+    /// that each channel receives its own color
+    pub fn apply(&self, color: &ColorOption) -> ColorOption {
+        let [first, second, third] = self.channels();
+        let reorder = |(r, g, b): (u8, u8, u8)| {
+            let value = |channel: ColorChannel| match channel {
+                ColorChannel::Red => r,
+                ColorChannel::Green => g,
+                ColorChannel::Blue => b,
+            };
+            (value(first), value(second), value(third))
+        };
+        match *color {
+            ColorOption::RGB(r, g, b) => {
+                let (r, g, b) = reorder((r, g, b));
+                ColorOption::RGB(r, g, b)
+            }
+            ColorOption::RGBW(r, g, b, w) => {
+                let (r, g, b) = reorder((r, g, b));
+                ColorOption::RGBW(r, g, b, w)
+            }
+            ColorOption::RGBWW(r, g, b, warm, cold) => {
+                let (r, g, b) = reorder((r, g, b));
+                ColorOption::RGBWW(r, g, b, warm, cold)
+            }
+        }
+    }
+}
+
+//-------//
+// Guide //
+//-------//
+/// Question asked by a `ChannelOrderGuide`: flash `color`
+/// on the device and answer `prompt` with the color seen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelQuestion {
+    pub color: ColorOption,
+    pub prompt: String,
+}
+
+/// Identifies the channel order of a device by flashing its
+/// channels one at a time and asking which color is seen; the
+/// last channel is deduced from the first two.
+///
+/// The colors of the questions are meant for the raw channels:
+/// reset the order of the device to `ChannelOrder::RGB` first.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::device::{ChannelOrder, ChannelOrderGuide, ColorChannel};
+///
+/// let mut guide = ChannelOrderGuide::new();
+/// // first channel flashed, the strip shows green
+/// assert_eq!(guide.question().unwrap().color.to_hex(), "#ff0000");
+/// guide.answer(ColorChannel::Green);
+/// // a color already seen is ignored
+/// guide.answer(ColorChannel::Green);
+/// assert_eq!(guide.question().unwrap().color.to_hex(), "#00ff00");
+/// guide.answer(ColorChannel::Red);
+///
+/// assert_eq!(guide.question(), None);
+/// assert_eq!(guide.order(), Some(ChannelOrder::GRB));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChannelOrderGuide {
+    // colors seen on the channels flashed so far
+    seen: Vec<ColorChannel>,
+}
+
+impl ChannelOrderGuide {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current question, `None` once the order is known
+    pub fn question(&self) -> Option<ChannelQuestion> {
+        let index = self.seen.len();
+        if index >= 2 {
+            return None;
+        }
+        let mut channels = [0; 3];
+        channels[index] = 255;
+        Some(ChannelQuestion {
+            color: ColorOption::RGB(channels[0], channels[1], channels[2]),
+            prompt: format!(
+                "Which color is shown: {:?}, {:?} or {:?}?",
+                ColorChannel::Red,
+                ColorChannel::Green,
+                ColorChannel::Blue
+            ),
+        })
+    }
+
+    /// Answers the current question with the color seen;
+    /// a color already seen on another channel is ignored
+    pub fn answer(&mut self, seen: ColorChannel) {
+        if self.seen.len() < 2 && !self.seen.contains(&seen) {
+            self.seen.push(seen);
+        }
+    }
+
+    /// Order of the device, `None` until every question is answered
+    pub fn order(&self) -> Option<ChannelOrder> {
+        let [first, second] = self.seen[..] else {
+            return None;
+        };
+        let third = [ColorChannel::Red, ColorChannel::Green, ColorChannel::Blue]
+            .into_iter()
+            .find(|channel| !self.seen.contains(channel))?;
+        ChannelOrder::from_channels([first, second, third])
+    }
+}
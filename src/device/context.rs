@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use btleplug::api::Characteristic;
+
+use crate::device::{Calibration, ChannelOrder, CharRole, StateCache, Transport};
+
+/// Per-device settings and state used by the capabilities: characteristics
+/// by role, write path, color calibration, channel wiring and last state.
+///
+/// A `Device` only stores it and exposes it through `Device::context`
+/// and `Device::context_mut`, the related `Device` methods
+/// (`transport`, `calibration`, `char_with_role`...) are provided on top.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::device::{ChannelOrder, DeviceContext};
+///
+/// let context = DeviceContext::default();
+///
+/// assert_eq!(context.channel_order, ChannelOrder::default());
+/// assert!(context.read_char.is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct DeviceContext {
+    /// Characteristics cached by role, for multi-characteristic devices
+    pub role_chars: HashMap<CharRole, Characteristic>,
+    /// Read characteristic set with `Device::set_read_char`,
+    /// takes precedence over `Device::read_char`
+    pub read_char: Option<Characteristic>,
+    /// Write path settings (MTU, pacing)
+    pub transport: Transport,
+    /// Color calibration applied before encoding
+    pub calibration: Calibration,
+    /// Wiring of the color channels
+    pub channel_order: ChannelOrder,
+    /// State written by the capabilities
+    pub state_cache: StateCache,
+}
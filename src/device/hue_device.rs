@@ -0,0 +1,206 @@
+use btleplug::api::{Characteristic, Peripheral as _};
+use btleplug::platform::Peripheral;
+
+use uuid::Uuid;
+
+use std::fmt;
+
+use crate::capability::light::LightOption;
+use crate::communication_protocol::HueBle;
+use crate::device::{CharRole, CharTarget, Device, DeviceContext, Write};
+use crate::error::BluetoothError;
+use crate::units::{Kelvin, Level};
+
+/// _Philips Hue_ Bluetooth bulb.
+///
+/// Hue bulbs expose one characteristic per function, resolved with
+/// `HueDevice::resolve_characteristics()` once connected; the capabilities
+/// driven through the `HueBle` protocol are then routed to the matching
+/// characteristic.
+///
+/// ## Examples
+/// ```compile_fail
+///    let mut controller = Controller::<HueDevice>::new_with_prefix("Hue").await?;
+///    controller.connect().await?;
+///
+///    let protocol = HueBle::default();
+///    for bulb in controller.list().iter_mut() {
+///        bulb.resolve_characteristics()?;
+///        bulb.turn_on(&protocol).await?;
+///        bulb.color(&protocol, 255, 0, 0).await?;
+///        println!("{:?}", bulb.read_state().await?);
+///    }
+/// ```
+#[derive(Debug)]
+pub struct HueDevice {
+    // BLE localname mapping
+    pub name: String,
+    // user-settable alias
+    pub alias: String,
+
+    // underlying BLE Pheripheral
+    peripheral: Option<Peripheral>,
+
+    // default communication chars
+    write_char: Option<Characteristic>,
+    read_char: Option<Characteristic>,
+
+    // per-role chars, write path settings and state
+    context: DeviceContext,
+}
+
+/// State read back from a _Hue_ bulb.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HueState {
+    pub on: bool,
+    pub brightness: Level,
+    pub temperature: Kelvin,
+    // CIE xy
+    pub xy: (f32, f32),
+}
+
+impl HueDevice {
+    /// Caches the light control characteristics (see `HueBle::characteristics`)
+    /// among the discovered ones; requires the device to be connected.
+    pub fn resolve_characteristics(&mut self) -> Result<(), BluetoothError> {
+        for (role, uuid) in HueBle::characteristics() {
+            let characteristic = self.find_char(&uuid)?;
+            self.set_char_with_role(role, &characteristic);
+        }
+        Ok(())
+    }
+
+    /// Sets a white light at the given temperature
+    pub async fn temperature(&self, kelvin: Kelvin) -> Result<(), BluetoothError> {
+        self.push_to(
+            &CharTarget::Role(CharRole::Temperature),
+            &HueBle::default().temperature(kelvin),
+        )
+        .await
+    }
+
+    /// Reads back the current state of the bulb, confirming
+    /// the power and brightness of the state cache
+    pub async fn read_state(&self) -> Result<HueState, BluetoothError> {
+        let state = HueState {
+            on: HueBle::decode_power(&self._read(&CharRole::Power).await?)
+                .ok_or(BluetoothError::InvalidResponse)?,
+            brightness: HueBle::decode_brightness(&self._read(&CharRole::Brightness).await?)
+                .ok_or(BluetoothError::InvalidResponse)?,
+            temperature: HueBle::decode_temperature(&self._read(&CharRole::Temperature).await?)
+                .ok_or(BluetoothError::InvalidResponse)?,
+            xy: HueBle::decode_xy(&self._read(&CharRole::Color).await?)
+                .ok_or(BluetoothError::InvalidResponse)?,
+        };
+        // the color is written as chromaticity, it is not read back
+        self.state_cache().confirm_with(|cached| {
+            cached.power = Some(match state.on {
+                true => LightOption::On,
+                false => LightOption::Off,
+            });
+            cached.brightness = Some(state.brightness);
+        });
+        Ok(state)
+    }
+
+    async fn _read(&self, role: &CharRole) -> Result<Vec<u8>, BluetoothError> {
+        Ok(self
+            .peripheral
+            .as_ref()
+            .ok_or(BluetoothError::InvalidPeripheralReference)?
+            .read(
+                self.char_with_role(role)
+                    .ok_or(BluetoothError::InvalidCharacteristic)?,
+            )
+            .await?)
+    }
+}
+
+impl Device for HueDevice {
+    fn new(
+        name: &str,
+        alias: &str,
+        peripheral: Option<Peripheral>,
+        write_char: Option<Characteristic>,
+        read_char: Option<Characteristic>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            alias: alias.to_string(),
+            peripheral,
+            write_char,
+            read_char,
+            context: DeviceContext::default(),
+        }
+    }
+    //--------//
+    // Getter //
+    //--------//
+    /// Provides access to __user-settable__
+    /// device alias
+    fn alias(&self) -> &str {
+        &self.alias
+    }
+    /// Provides access to the device local_name
+    fn name(&self) -> &str {
+        &self.name
+    }
+    /// Provides access to _BLE-specific_ MAC device address
+    fn address(&self) -> Option<String> {
+        self.peripheral
+            .as_ref()
+            .map(|peripheral| peripheral.address().to_string())
+    }
+    fn peripheral(&self) -> Option<&Peripheral> {
+        self.peripheral.as_ref()
+    }
+    fn write_char(&self) -> Option<&Characteristic> {
+        self.write_char.as_ref()
+    }
+    fn read_char(&self) -> Option<&Characteristic> {
+        self.read_char.as_ref()
+    }
+    fn context(&self) -> &DeviceContext {
+        &self.context
+    }
+    fn default_write_characteristic_uuid(&self) -> Uuid {
+        HueBle::characteristics()[0].1
+    }
+
+    //--------//
+    // Setter //
+    //--------//
+    /// Allows to set __user-settable__
+    /// device alias
+    fn set_alias(&mut self, alias: &str) {
+        self.alias = alias.to_string();
+    }
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+    fn set_peripheral(&mut self, peripheral: Peripheral) {
+        self.peripheral = Some(peripheral);
+    }
+    fn set_write_char(&mut self, characteristic: &Characteristic) {
+        self.write_char = Some(characteristic.clone());
+    }
+    fn set_read_char(&mut self, characteristic: &Characteristic) {
+        self.read_char = Some(characteristic.clone());
+    }
+    fn context_mut(&mut self) -> &mut DeviceContext {
+        &mut self.context
+    }
+}
+//--------------//
+// Display impl //
+//--------------//
+impl fmt::Display for HueDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.name(),
+            self.address().unwrap_or(String::from("-"))
+        )
+    }
+}
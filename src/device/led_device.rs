@@ -5,7 +5,7 @@ use uuid::Uuid;
 
 use std::fmt;
 
-use crate::device::Device;
+use crate::device::{Device, DeviceContext};
 
 #[derive(Debug)]
 pub struct LedDevice {
@@ -20,6 +20,9 @@ pub struct LedDevice {
     // default communication chars
     write_char: Option<Characteristic>,
     read_char: Option<Characteristic>,
+
+    // per-role chars, write path settings and state
+    context: DeviceContext,
 }
 
 impl Device for LedDevice {
@@ -36,6 +39,7 @@ impl Device for LedDevice {
             peripheral,
             write_char: write_char.clone(),
             read_char: read_char.clone(),
+            context: DeviceContext::default(),
         }
     }
     //--------//
@@ -66,6 +70,9 @@ impl Device for LedDevice {
     fn read_char(&self) -> Option<&Characteristic> {
         self.read_char.as_ref()
     }
+    fn context(&self) -> &DeviceContext {
+        &self.context
+    }
     fn default_write_characteristic_uuid(&self) -> Uuid {
         unimplemented!()
     }
@@ -87,6 +94,12 @@ impl Device for LedDevice {
     fn set_write_char(&mut self, characteristic: &Characteristic) {
         self.write_char = Some(characteristic.clone());
     }
+    fn set_read_char(&mut self, characteristic: &Characteristic) {
+        self.read_char = Some(characteristic.clone());
+    }
+    fn context_mut(&mut self) -> &mut DeviceContext {
+        &mut self.context
+    }
 }
 //--------------//
 // Display impl //
@@ -1,26 +1,47 @@
+use crate::capability::color::device_color;
+use crate::communication_protocol::{CommandKind, DeviceState, Protocol, StateProtocol};
 use crate::error::BluetoothError;
+use crate::units::Level;
 
 use btleplug::api::Characteristic;
-use btleplug::api::{Peripheral as _, WriteType};
+use btleplug::api::{CharPropFlags, Peripheral as _, WriteType};
 use btleplug::platform::Peripheral;
 
 use uuid::Uuid;
 
 use async_trait::async_trait;
 use enumflags2::{bitflags, BitFlags};
+use futures::stream::{Stream, StreamExt};
 use std::fmt;
+use std::pin::Pin;
+use std::time::Duration;
 
 //----------//
 // Re-export//
 //----------//
 ////////////////////////////////////
+pub use self::calibration::{Answer, Calibration, CalibrationGuide, CalibrationQuestion};
+pub use self::channel_order::{ChannelOrder, ChannelOrderGuide, ChannelQuestion, ColorChannel};
+pub use self::context::DeviceContext;
+pub use self::hue_device::HueDevice;
 pub use self::led_device::LedDevice;
+pub use self::state::{LightMode, LightState, StateCache};
+pub use self::transport::Transport;
 ////////////////////////////////////
 
+pub mod calibration;
+pub mod channel_order;
+pub mod context;
+pub mod hue_device;
 pub mod led_device;
+pub mod state;
+pub mod transport;
 
 const BT_BASE_UUID: u128 = 0x00000000_0000_1000_8000_00805f9b34fb;
 
+// time left to the device to answer a state query
+const STATE_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
 // Wrapper for native ble charprops
 /// Describe the _operation kind_ supported
 /// by each characteristic. Each `OpKind` can piped.
@@ -61,6 +82,53 @@ pub enum CharKind {
     Write,
 }
 
+/// Logical role of a characteristic; allows a protocol to target
+/// one of the characteristics of a multi-characteristic device
+/// without knowing its UUID.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CharRole {
+    Power,
+    Color,
+    Brightness,
+    Temperature,
+    Effect,
+    Control,
+    Named(String),
+}
+
+/// Characteristic a frame is written to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharTarget {
+    /// The device default write characteristic
+    Default,
+    /// The characteristic cached under the role, see `Device::set_role_char`
+    Role(CharRole),
+    /// Any discovered characteristic
+    Uuid(Uuid),
+}
+
+impl UuidKind {
+    /// Expands the short forms to a full _Bluetooth_ UUID
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::device::UuidKind;
+    ///
+    /// assert_eq!(
+    ///     UuidKind::Uuid16(0xFFD9).to_uuid().to_string(),
+    ///     "0000ffd9-0000-1000-8000-00805f9b34fb"
+    /// );
+    /// ```
+    pub fn to_uuid(&self) -> Uuid {
+        match self {
+            UuidKind::Uuid(uuid) => *uuid,
+            UuidKind::Uuid128(uuid) => Uuid::from_u128(*uuid),
+            UuidKind::Uuid32(uuid) => Uuid::from_u128(BT_BASE_UUID | ((*uuid as u128) << 96)),
+            UuidKind::Uuid16(uuid) => Uuid::from_u128(BT_BASE_UUID | ((*uuid as u128) << 96)),
+        }
+    }
+}
+
 pub trait Device: fmt::Display {
     fn new(
         name: &str,
@@ -79,6 +147,51 @@ pub trait Device: fmt::Display {
     fn write_char(&self) -> Option<&Characteristic>;
     fn read_char(&self) -> Option<&Characteristic>;
     fn default_write_characteristic_uuid(&self) -> Uuid;
+    /// Settings and state used by the capabilities, see `DeviceContext`
+    fn context(&self) -> &DeviceContext;
+
+    /// Characteristic cached under the role, see `Device::set_role_char`
+    fn char_with_role(&self, role: &CharRole) -> Option<&Characteristic> {
+        self.context().role_chars.get(role)
+    }
+    /// Characteristic notifications are received from: the one
+    /// set with `Device::set_read_char`, `Device::read_char` otherwise
+    fn notify_char(&self) -> Option<&Characteristic> {
+        self.context()
+            .read_char
+            .as_ref()
+            .or_else(|| self.read_char())
+    }
+    /// Provides access to the write path settings (MTU, pacing)
+    fn transport(&self) -> &Transport {
+        &self.context().transport
+    }
+    /// Color calibration applied before encoding (gamma, white balance)
+    fn calibration(&self) -> &Calibration {
+        &self.context().calibration
+    }
+    /// Wiring of the color channels, applied after calibration
+    fn channel_order(&self) -> ChannelOrder {
+        self.context().channel_order
+    }
+    /// State written by the capabilities, see `Device::state`
+    fn state_cache(&self) -> &StateCache {
+        &self.context().state_cache
+    }
+
+    /// Snapshot of the state of the device: power, color, brightness
+    /// and mode as last set through the capabilities, or reported by
+    /// the device (see `QueryState`).
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    light.turn_on(&protocol).await?;
+    ///    light.color(&protocol, 255, 0, 0).await?;
+    ///    println!("{:?}", light.state().color);
+    /// ```
+    fn state(&self) -> LightState {
+        self.state_cache().snapshot()
+    }
 
     /// Return all the discovered device characteristic.
     ///
@@ -109,7 +222,7 @@ pub trait Device: fmt::Display {
     /// ## Examples
     /// ```compile_fail
     ///       let char_kind_filter = OpKind::Write | OpKind::WriteWithoutResponse;
-
+    ///
     ///       for characteristic in light
     ///           .characteristics_by_type(char_kind_filter)
     ///           .unwrap()
@@ -140,6 +253,42 @@ pub trait Device: fmt::Display {
     fn set_name(&mut self, name: &str);
     fn set_peripheral(&mut self, peripheral: Peripheral);
 
+    /// Allows to change the settings of the device, see `DeviceContext`
+    fn context_mut(&mut self) -> &mut DeviceContext;
+
+    /// Allows to change the write path settings (MTU, pacing)
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    light.transport_mut().set_mtu(244);
+    ///    light.transport_mut().set_pacing(Duration::from_millis(10));
+    /// ```
+    fn transport_mut(&mut self) -> &mut Transport {
+        &mut self.context_mut().transport
+    }
+
+    /// Allows to change the color calibration, e.g. with
+    /// the profile produced by a `CalibrationGuide`
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    *light.calibration_mut() = guide.calibration();
+    /// ```
+    fn calibration_mut(&mut self) -> &mut Calibration {
+        &mut self.context_mut().calibration
+    }
+
+    /// Allows to set the wiring of the color channels, e.g. with
+    /// the order identified by a `ChannelOrderGuide`
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    light.set_channel_order(ChannelOrder::GRB);
+    /// ```
+    fn set_channel_order(&mut self, order: ChannelOrder) {
+        self.context_mut().channel_order = order;
+    }
+
     /// Allows to set the default characteristic (Write or Read),
     /// per-device by providing the `Characteristic`.
     ///
@@ -150,6 +299,64 @@ pub trait Device: fmt::Display {
     /// ```
     fn set_write_char(&mut self, characteristic: &Characteristic);
 
+    /// Allows to set the read characteristic, notifications
+    /// (see `Notify`) are received from it.
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    light.set_char(&CharKind::Read, &UuidKind::Uuid16(0xFFD4))?;
+    /// ```
+    fn set_read_char(&mut self, characteristic: &Characteristic) {
+        self.context_mut().read_char = Some(characteristic.clone());
+    }
+
+    /// Caches the `Characteristic` under the given role,
+    /// frames targeting `CharTarget::Role(role)` are written to it.
+    fn set_char_with_role(&mut self, role: CharRole, characteristic: &Characteristic) {
+        self.context_mut()
+            .role_chars
+            .insert(role, characteristic.clone());
+    }
+
+    /// Resolves the characteristic with the given `UuidKind` among the discovered
+    /// ones and caches it under the given role.
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    light.set_role_char(CharRole::Power, &UuidKind::Uuid16(0xFFE1))?;
+    ///    light.set_role_char(CharRole::Color, &UuidKind::Uuid16(0xFFE2))?;
+    /// ```
+    fn set_role_char(
+        &mut self,
+        role: CharRole,
+        uuid_kind: &UuidKind,
+    ) -> Result<(), BluetoothError> {
+        let char = self.find_char(&uuid_kind.to_uuid())?;
+        self.set_char_with_role(role, &char);
+        Ok(())
+    }
+
+    /// Looks up a discovered characteristic by UUID
+    fn find_char(&self, uuid: &Uuid) -> Result<Characteristic, BluetoothError> {
+        self.peripheral()
+            .as_ref()
+            .ok_or(BluetoothError::InvalidPeripheralReference)?
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid.as_u128() == uuid.as_u128())
+            .ok_or(BluetoothError::NotFoundTargetCharacteristic)
+    }
+
+    /// Resolves the characteristic a frame has to be written to
+    fn resolve_target(&self, target: &CharTarget) -> Result<Characteristic, BluetoothError> {
+        match target {
+            CharTarget::Default => self.write_char().cloned(),
+            CharTarget::Role(role) => self.char_with_role(role).cloned(),
+            CharTarget::Uuid(uuid) => return self.find_char(uuid),
+        }
+        .ok_or(BluetoothError::InvalidCharacteristic)
+    }
+
     /// Allows to set the default characteristic (Write or Read),
     /// per-device by providing the `UuidKind` of the characteristic.
     ///
@@ -163,34 +370,17 @@ pub trait Device: fmt::Display {
         char_kind: &CharKind,
         uuid_kind: &UuidKind,
     ) -> Result<(), BluetoothError> {
-        match char_kind {
-            CharKind::Write => match uuid_kind {
-                UuidKind::Uuid(uuid) => self.set_char_with_uuid(char_kind, &uuid),
-                UuidKind::Uuid128(uuid) => {
-                    self.set_char_with_uuid(char_kind, &Uuid::from_u128(*uuid))
-                }
-                UuidKind::Uuid32(uuid) => self.set_char_with_u32(char_kind, *uuid),
-                UuidKind::Uuid16(uuid) => self.set_char_with_u16(char_kind, *uuid),
-            },
-            CharKind::Read => unimplemented!(),
-        }
+        self.set_char_with_uuid(char_kind, &uuid_kind.to_uuid())
     }
     fn set_char_with_uuid(
         &mut self,
         char_kind: &CharKind,
         uuid: &Uuid,
     ) -> Result<(), BluetoothError> {
-        let char = self
-            .peripheral()
-            .as_ref()
-            .ok_or(BluetoothError::InvalidPeripheralReference)?
-            .characteristics()
-            .into_iter()
-            .find(|c| c.uuid.as_u128() == uuid.as_u128())
-            .ok_or(BluetoothError::NotFoundTargetCharacteristic)?;
+        let char = self.find_char(uuid)?;
         match char_kind {
             CharKind::Write => self.set_write_char(&char),
-            CharKind::Read => unimplemented!(),
+            CharKind::Read => self.set_read_char(&char),
         }
         Ok(())
     }
@@ -198,8 +388,7 @@ pub trait Device: fmt::Display {
         self.set_char_with_u32(char_kind, u16 as u32) // extend it to 32 bits
     }
     fn set_char_with_u32(&mut self, char_kind: &CharKind, u32: u32) -> Result<(), BluetoothError> {
-        let uuid = Uuid::from_u128(BT_BASE_UUID | ((u32 as u128) << 96));
-        self.set_char_with_uuid(char_kind, &uuid)
+        self.set_char_with_uuid(char_kind, &UuidKind::Uuid32(u32).to_uuid())
     }
 }
 
@@ -208,9 +397,60 @@ pub trait Disconnect {
     async fn leave(&self) -> Result<(), BluetoothError>;
 }
 pub trait Connect {}
+
+/// Stream of the values notified by a characteristic
+pub type Notifications = Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>;
+
+#[async_trait]
+pub trait Notify {
+    /// Subscribes to the read characteristic and
+    /// returns the stream of the values it notifies.
+    async fn notifications(&self) -> Result<Notifications, BluetoothError>;
+    /// Unsubscribes from the read characteristic, once the
+    /// notifications are no longer needed.
+    async fn unsubscribe(&self) -> Result<(), BluetoothError>;
+}
+#[async_trait]
+pub trait QueryState {
+    /// Queries the device and decodes the state it reports, reflecting
+    /// changes made by other means (IR remote, vendor app). Requires
+    /// the read characteristic to be set; it is subscribed to for the
+    /// answer only, and unsubscribed afterwards.
+    ///
+    /// ## Examples
+    /// ```compile_fail
+    ///    light.set_char(&CharKind::Read, &UuidKind::Uuid16(GenericRGB::NOTIFY_CHAR_UUID16))?;
+    ///    let state = light.query_state(&GenericRGB::default()).await?;
+    ///    println!("{:?}", state.color);
+    /// ```
+    async fn query_state<P: StateProtocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &P,
+    ) -> Result<DeviceState, BluetoothError>;
+}
 #[async_trait]
 pub trait Write {
+    /// Writes to the default write characteristic
     async fn push(&self, raw_bytes: &[u8]) -> Result<(), BluetoothError>;
+    /// Writes to the targeted characteristic, split
+    /// into packets of at most `Transport::mtu` bytes
+    async fn push_to(&self, target: &CharTarget, raw_bytes: &[u8]) -> Result<(), BluetoothError>;
+    /// Writes the packets in order to the targeted characteristic, paced by
+    /// `Transport::pacing`; packets of concurrent writes are not interleaved.
+    async fn push_packets(
+        &self,
+        target: &CharTarget,
+        packets: &[Vec<u8>],
+    ) -> Result<(), BluetoothError>;
+    /// Writes a frame encoded by `protocol` to the characteristic targeted by
+    /// the command, split into packets by `Protocol::packets`; fails if the
+    /// protocol is not `Protocol::ready`
+    async fn push_command<P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &P,
+        command: &CommandKind,
+        frame: &[u8],
+    ) -> Result<(), BluetoothError>;
 }
 
 //-------------------------//
@@ -228,21 +468,160 @@ impl<D: Device + std::marker::Sync> Disconnect for D {
     }
 }
 
+#[async_trait]
+impl<D: Device + std::marker::Sync> Notify for D {
+    async fn notifications(&self) -> Result<Notifications, BluetoothError> {
+        let peripheral = self
+            .peripheral()
+            .ok_or(BluetoothError::InvalidPeripheralReference)?;
+        let characteristic = self
+            .notify_char()
+            .ok_or(BluetoothError::InvalidCharacteristic)?
+            .clone();
+
+        peripheral.subscribe(&characteristic).await?;
+        let notifications = peripheral
+            .notifications()
+            .await?
+            .filter_map(move |notification| {
+                let value = (notification.uuid.as_u128() == characteristic.uuid.as_u128())
+                    .then_some(notification.value);
+                async move { value }
+            });
+        Ok(Box::pin(notifications))
+    }
+
+    async fn unsubscribe(&self) -> Result<(), BluetoothError> {
+        if let (Some(peripheral), Some(characteristic)) = (self.peripheral(), self.notify_char()) {
+            peripheral.unsubscribe(characteristic).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<D: Device + std::marker::Sync> QueryState for D {
+    async fn query_state<P: StateProtocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &P,
+    ) -> Result<DeviceState, BluetoothError> {
+        let mut notifications = self.notifications().await?;
+        let state = async {
+            self.push_command(protocol, &CommandKind::Query, &protocol.query_state())
+                .await?;
+            tokio::time::timeout(STATE_QUERY_TIMEOUT, async {
+                while let Some(value) = notifications.next().await {
+                    if let Some(state) = protocol.decode_state(&value) {
+                        _confirm_state(self, protocol, &state);
+                        return Ok(state);
+                    }
+                }
+                Err(BluetoothError::InvalidResponse)
+            })
+            .await
+            .map_err(|_| BluetoothError::Timeout)?
+        }
+        .await;
+
+        // subscribed for this answer only, whatever the outcome
+        drop(notifications);
+        let unsubscribed = self.unsubscribe().await;
+        let state = state?;
+        unsubscribed?;
+        Ok(state)
+    }
+}
+
+// Confirms the cached state with the reported one, compared as written
+// to the device: calibrated, reordered and scaled by the level of
+// protocols without a native brightness. The channel order of a
+// mismatching color is undone, its calibration is kept.
+fn _confirm_state<D: Device, P: Protocol>(device: &D, protocol: &P, reported: &DeviceState) {
+    let cached = device.state_cache().snapshot();
+    let level = match protocol.native_brightness() {
+        true => Level::FULL,
+        false => cached.brightness.unwrap_or(Level::FULL),
+    };
+    let shown = cached
+        .color
+        .map(|color| device_color(device, protocol, &color.scaled(level)).rgb());
+    let color = (shown != Some(reported.color.rgb()))
+        .then(|| device.channel_order().inverse().apply(&reported.color));
+    device
+        .state_cache()
+        .confirm(reported.light, color, protocol.native_brightness());
+}
+
 #[async_trait]
 impl<D: Device + std::marker::Sync> Write for D {
     async fn push(&self, raw_bytes: &[u8]) -> Result<(), BluetoothError> {
-        //TODO: Implement different WriteType(s)
-        self.peripheral()
-            .as_ref()
-            .ok_or(BluetoothError::InvalidPeripheralReference)?
-            .write(
-                self.write_char()
-                    .ok_or(BluetoothError::InvalidCharacteristic)?,
-                raw_bytes,
-                WriteType::WithoutResponse,
-            )
-            .await?;
+        self.push_to(&CharTarget::Default, raw_bytes).await
+    }
 
-        Ok(())
+    async fn push_to(&self, target: &CharTarget, raw_bytes: &[u8]) -> Result<(), BluetoothError> {
+        let packets = raw_bytes
+            .chunks(self.transport().mtu())
+            .map(<[u8]>::to_vec)
+            .collect::<Vec<Vec<u8>>>();
+        self.push_packets(target, &packets).await
+    }
+
+    async fn push_packets(
+        &self,
+        target: &CharTarget,
+        packets: &[Vec<u8>],
+    ) -> Result<(), BluetoothError> {
+        let characteristic = self.resolve_target(target)?;
+        let peripheral = self
+            .peripheral()
+            .ok_or(BluetoothError::InvalidPeripheralReference)?;
+
+        // held until the last packet is written
+        let _guard = self.transport().lock().await;
+        write_packets(
+            peripheral,
+            &characteristic,
+            packets,
+            self.transport().pacing(),
+        )
+        .await
+    }
+
+    async fn push_command<P: Protocol + std::marker::Send + std::marker::Sync>(
+        &self,
+        protocol: &P,
+        command: &CommandKind,
+        frame: &[u8],
+    ) -> Result<(), BluetoothError> {
+        protocol.ready()?;
+        let packets = protocol.packets(frame, self.transport().mtu());
+        self.push_packets(&protocol.target(command), &packets).await
+    }
+}
+
+// Writes the packets in order, `pacing` apart; the caller holds the write
+// lock of the transport (see `Transport::shared_lock` for background tasks)
+pub(crate) async fn write_packets(
+    peripheral: &Peripheral,
+    characteristic: &Characteristic,
+    packets: &[Vec<u8>],
+    pacing: Duration,
+) -> Result<(), BluetoothError> {
+    // prefer unacknowledged writes when supported
+    let write_type = if characteristic
+        .properties
+        .contains(CharPropFlags::WRITE_WITHOUT_RESPONSE)
+    {
+        WriteType::WithoutResponse
+    } else {
+        WriteType::WithResponse
+    };
+
+    for (index, packet) in packets.iter().enumerate() {
+        if index > 0 && !pacing.is_zero() {
+            tokio::time::sleep(pacing).await;
+        }
+        peripheral.write(characteristic, packet, write_type).await?;
     }
+    Ok(())
 }
@@ -0,0 +1,141 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::capability::color::ColorOption;
+use crate::capability::light::LightOption;
+use crate::units::{Kelvin, Level};
+
+/// What a device was last set to, see `LightState::mode`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LightMode {
+    /// Steady color, set by `Color` or `Brightness`
+    Color,
+    /// White at a color temperature, set by `Temperature`
+    Temperature(Kelvin),
+    /// Built-in animation, by name (e.g. `Pulsating Red`),
+    /// with the period of one cycle when the protocol knows it
+    HWAnimation {
+        name: String,
+        period: Option<Duration>,
+    },
+    /// Software animation driven by the library, by name
+    SWAnimation(String),
+    /// Individual pixels, set by `Pixels`
+    Pixels,
+    /// Microphone mode of the controller, by pattern, set by `HWMusic`
+    Music(String),
+}
+
+/// Snapshot of the state of a device, as last written by the
+/// capabilities (see `Device::state`); `None` fields are unknown.
+///
+/// The state is `confirmed` when it was last updated from the
+/// state reported by the device (see `QueryState`), it only
+/// reflects what was sent otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LightState {
+    pub power: Option<LightOption>,
+    /// Color at full brightness
+    pub color: Option<ColorOption>,
+    pub brightness: Option<Level>,
+    pub mode: Option<LightMode>,
+    pub updated: Option<SystemTime>,
+    pub confirmed: bool,
+}
+
+/// State of a device, updated by every successful capability call.
+///
+/// Protocols without a native brightness command (see
+/// `Protocol::native_brightness`) change the level by scaling the
+/// last color, white channels included; white is assumed
+/// until a color is set.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::capability::color::ColorOption;
+/// use ble_ledly::device::{LightMode, StateCache};
+/// use ble_ledly::units::Level;
+///
+/// let cache = StateCache::default();
+/// assert_eq!(cache.color(), None);
+///
+/// // seeded with a known state
+/// cache.update(|state| {
+///     state.color = Some(ColorOption::RGB(255, 128, 0));
+///     state.brightness = Some(Level::new(0.5).unwrap());
+///     state.mode = Some(LightMode::Color);
+/// });
+/// assert_eq!(cache.color(), Some(ColorOption::RGB(255, 128, 0)));
+/// assert_eq!(cache.brightness(), Level::new(0.5).ok());
+///
+/// let state = cache.snapshot();
+/// assert!(state.updated.is_some());
+/// assert!(!state.confirmed);
+/// ```
+#[derive(Debug, Default)]
+pub struct StateCache {
+    state: Mutex<LightState>,
+}
+
+impl StateCache {
+    /// Current state
+    pub fn snapshot(&self) -> LightState {
+        // the state stays consistent even if a writer panicked
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+    /// Last color set, at full brightness
+    pub fn color(&self) -> Option<ColorOption> {
+        self.snapshot().color
+    }
+    /// Last brightness level set
+    pub fn brightness(&self) -> Option<Level> {
+        self.snapshot().brightness
+    }
+
+    /// Updates the state with what was sent to the device;
+    /// the state is then no longer `confirmed`
+    pub fn update(&self, update: impl FnOnce(&mut LightState)) {
+        self._update(false, update);
+    }
+
+    // Updates the state with the one reported by the device; `color` is
+    // the reported color, back in the channel order of the user, when it
+    // differs from the cached one as written to the device. It includes
+    // the brightness of protocols scaling the color.
+    pub(crate) fn confirm(
+        &self,
+        power: LightOption,
+        color: Option<ColorOption>,
+        native_brightness: bool,
+    ) {
+        self.confirm_with(|state| {
+            state.power = Some(power);
+            if let Some(color) = color {
+                state.color = Some(color);
+                if !native_brightness {
+                    state.brightness = Some(Level::FULL);
+                }
+            }
+        });
+    }
+
+    // Updates the state with values read back from the device
+    pub(crate) fn confirm_with(&self, update: impl FnOnce(&mut LightState)) {
+        self._update(true, update);
+    }
+
+    fn _update(&self, confirmed: bool, update: impl FnOnce(&mut LightState)) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        update(&mut state);
+        state.updated = Some(SystemTime::now());
+        state.confirmed = confirmed;
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, MutexGuard};
+
+// ATT_MTU (23) minus the write request header (3)
+const DEFAULT_MTU: usize = 20;
+
+/// Write path settings of a device.
+///
+/// Frames larger than the `mtu` are split into packets (see `Protocol::packets`),
+/// written in order and optionally paced; packets of concurrent writes
+/// to the same device are never interleaved.
+///
+/// ## Examples
+/// ```
+/// use ble_ledly::device::Transport;
+/// use std::time::Duration;
+///
+/// let mut transport = Transport::default();
+/// assert_eq!(transport.mtu(), 20);
+///
+/// // larger MTU negotiated by the platform
+/// transport.set_mtu(244);
+/// transport.set_pacing(Duration::from_millis(10));
+/// assert_eq!(transport.pacing(), Duration::from_millis(10));
+/// ```
+#[derive(Debug)]
+pub struct Transport {
+    // payload bytes per write
+    mtu: usize,
+    // delay between the packets of a frame
+    pacing: Duration,
+    // serializes the writes of concurrent callers,
+    // shared with the background writers (e.g. keep-alive)
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self {
+            mtu: DEFAULT_MTU,
+            pacing: Duration::ZERO,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+}
+
+impl Transport {
+    /// Maximum number of bytes written at once
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+    /// Delay between the packets of a fragmented frame
+    pub fn pacing(&self) -> Duration {
+        self.pacing
+    }
+
+    /// Sets the number of bytes written at once, that is the negotiated
+    /// _ATT MTU_ minus 3; defaults to `20`.
+    pub fn set_mtu(&mut self, mtu: usize) {
+        self.mtu = mtu.max(1);
+    }
+    /// Sets the delay between the packets of a fragmented frame,
+    /// for controllers dropping back-to-back writes.
+    pub fn set_pacing(&mut self, pacing: Duration) {
+        self.pacing = pacing;
+    }
+
+    pub(crate) async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.write_lock.lock().await
+    }
+
+    // for tasks writing to the device in the background
+    pub(crate) fn shared_lock(&self) -> Arc<Mutex<()>> {
+        Arc::clone(&self.write_lock)
+    }
+}
@@ -26,4 +26,129 @@ pub enum BluetoothError {
 
     #[error("Invalid or absent characteristic")]
     InvalidCharacteristic,
+
+    #[error("Invalid or malformed response from the device")]
+    InvalidResponse,
+
+    #[error("The device did not answer in time")]
+    Timeout,
+
+    #[error("The device rejected the handshake")]
+    HandshakeFailed,
+
+    #[error("`{0}` is not supported by the protocol")]
+    Unsupported(String),
+
+    #[error("No session established with the device, run the handshake first")]
+    HandshakeRequired,
+
+    #[error("Payload of {0} bytes too long, at most {1} bytes")]
+    PayloadTooLong(usize, usize),
+
+    #[error("{0} steps, at most {1} steps")]
+    TooManySteps(usize, usize),
+
+    #[error("{0} pixels, at most {1} pixels")]
+    TooManyPixels(usize, usize),
+
+    #[error("Empty pixel range {0}..{1}")]
+    EmptyPixelRange(u16, u16),
+
+    #[error("Invalid protocol configuration: {0}")]
+    InvalidConfiguration(String),
+}
+
+/// Errors related to loading a declarative protocol definition
+#[derive(Error, Debug)]
+pub enum ProtocolDefinitionError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Unsupported protocol definition format `{0}`, expected `toml` or `json`")]
+    UnsupportedFormat(String),
+
+    #[error("Unable to parse protocol definition: {0}")]
+    Parse(String),
+
+    #[error("Invalid token `{token}` in the `{command}` template")]
+    InvalidToken { command: String, token: String },
+
+    #[error("Placeholder `{placeholder}` is not allowed in the `{command}` template")]
+    UnexpectedPlaceholder {
+        command: String,
+        placeholder: String,
+    },
+
+    #[error("The `{command}` template uses a checksum but no checksum is defined")]
+    MissingChecksum { command: String },
+
+    #[error("The checksum of the `{command}` template starts after the checksum itself")]
+    InvalidChecksumRange { command: String },
+
+    #[error("Expected {expected} animation speeds, found {found}")]
+    InvalidSpeedTable { expected: usize, found: usize },
+}
+
+/// Errors related to parsing a color, see `ble_ledly::color`
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ColorParseError {
+    #[error("Invalid hex color `{0}`, expected `#rrggbb` or `#rgb`")]
+    InvalidHex(String),
+
+    #[error("Invalid or out of range components in `{0}`")]
+    InvalidComponents(String),
+
+    #[error("Unknown color name `{0}`")]
+    UnknownName(String),
+}
+
+/// Errors related to validating a unit, see `ble_ledly::units`
+#[derive(Error, Debug, PartialEq)]
+pub enum UnitError {
+    #[error("Level {0} out of range, expected 0.0..=1.0")]
+    InvalidLevel(f32),
+
+    #[error("Percentage {0} out of range, expected 0..=100")]
+    InvalidPercent(u8),
+
+    #[error("Color temperature {0}K out of range, expected 1000..=40000K")]
+    InvalidKelvin(u16),
+}
+
+/// Errors related to loading a palette, see `Palette::from_file`
+#[derive(Error, Debug)]
+pub enum PaletteError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Unsupported palette format `{0}`, expected `toml` or `json`")]
+    UnsupportedFormat(String),
+
+    #[error("Unable to parse palette: {0}")]
+    Parse(String),
+
+    #[error(transparent)]
+    Color(#[from] ColorParseError),
+
+    #[error("Palette `{0}` has no color")]
+    Empty(String),
+}
+
+/// Errors related to validating a frame, see `Framing::parse`
+#[derive(Error, Debug)]
+pub enum FramingError {
+    #[error("The frame is shorter than its layout")]
+    TooShort,
+
+    #[error("The frame does not start with the expected header")]
+    InvalidHeader,
+
+    #[error("The frame does not end with the expected footer")]
+    InvalidFooter,
+
+    #[error("The declared length does not match the frame")]
+    InvalidLength,
+
+    #[error("Checksum mismatch")]
+    InvalidChecksum,
 }
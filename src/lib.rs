@@ -30,11 +30,13 @@
 //! | Capability  | Description                                                                                   | Implemented? |
 //! |-------------|-----------------------------------------------------------------------------------------------|--------------|
 //! |` Light      ` | Light state (on/off)                                                                          |       ✅      |
-//! |` Color      ` | Light Color (RGB)                                                                             |       ✅      |
-//! |` Brightness ` | Light white levels                                                                            |       ✅      |
+//! |` Color      ` | Light Color (RGB/RGBW/RGBWW, hex, HSV, HSL, CIE xy, Kelvin, CSS names; see `color`)           |       ✅      |
+//! |` Brightness ` | Light levels, native or scaling the last color set (`set_brightness`)                         |       ✅      |
 //! |` HWAnimate  ` | Hardware specific animations (subjected to protocol)                                          |       ✅      |
 //! |` SWAnimate  ` | Software animation (require continuous communication, but allows custom effect on any device) |       ✅      |
-//! |` Temperature` | Light temperature (K)                                                                         |              |
+//! |` Pixels     ` | Addressable pixels, per-segment or full frame colors (subjected to protocol)                  |       ✅      |
+//! |` Temperature` | Light temperature (K), native when supported or blackbody color otherwise                     |       ✅      |
+//! |` HWMusic    ` | Built-in microphone mode, pattern and sensitivity (`music` feature, subjected to protocol)    |       ✅      |
 //!
 //!
 //! ## Animations
@@ -47,22 +49,51 @@
 //!
 //! | Animation         | Capability | Color Support                          | Implemented? |
 //! |-------------------|------------|----------------------------------------|--------------|
-//! | Pulsating         | HWAnimate  | Red/Green/Blue                         |       ✅      |
+//! | Pulsating         | HWAnimate  | Red, Green, Blue, Yellow, Cyan, Purple, White |       ✅      |
 //! | Breathing         | SWAnimate  | RGB/Any                                |       ✅      |
-//! | Rainbow Pulsating | HWAnimate  | N/A                                    |              |
-//! | Pulsating Bicolor | HWAnimate  | Red/Green, Red/Blue, Green/Blue        |              |
-//! | Rainbow flashing  | HWAnimate  | N/A                                    |              |
-//! | Cross-fade        | SWAnimate  | RGB/Any                                |              |
-//! | Rainbow jumping   | HWAnimate  | N/A                                    |              |
-//! | Flashing          | HWAnimate  | Red, Green, Blue, Yellow, Purple, Cyan |              |
+//! | Rainbow Pulsating | HWAnimate  | N/A                                    |       ✅      |
+//! | Pulsating Bicolor | HWAnimate  | Red/Green, Red/Blue, Green/Blue        |       ✅      |
+//! | Rainbow flashing  | HWAnimate  | N/A                                    |       ✅      |
+//! | Cross-fade        | SWAnimate  | Any `Palette`                          |       ✅      |
+//! | Rainbow jumping   | HWAnimate  | N/A                                    |       ✅      |
+//! | Flashing          | HWAnimate  | Red, Green, Blue, Yellow, Cyan, Purple, White |       ✅      |
+//!
+//! ## Protocols
+//!
+//! | Protocol     | Devices                                  | Write characteristic |
+//! |--------------|------------------------------------------|----------------------|
+//! | `GenericRGB` | Triones / QHM- generic RGB controllers   | `0xFFD9`             |
+//! | `ElkBledom`  | ELK-BLEDOM / Lotus Lamp controllers      | `0xFFF3`             |
+//! | `Zengge`     | Zengge LEDnet BLE devices (MagicHome)    | `0xFF01`             |
+//! | `Govee`      | Govee H6xxx strips and bulbs             | `00010203-0405-0607-0809-0a0b0c0d2b11` |
+//! | `HueBle`     | Philips Hue Bluetooth bulbs (`HueDevice`) | one per function, service `932c32bd-0000-47a2-835a-a8d455b859dd` |
+//! | `Sp110e`     | SP110E / SP6xxE addressable pixel controllers | `0xFFE1`        |
+//! | `Yeelight`   | Yeelight bedside / candela lamps (requires `Controller::handshake`) | `aa7d3f34-2d4f-41e0-807f-52fbf8cf7443` |
+//! | `TuyaBle`    | Tuya BLE encrypted lights (`tuya` feature, requires `Controller::handshake`) | `0x2B11` |
 //!
 //! ## Extensibility
 //!
 //! This library has been designed with _extensibility in mind_.
 //!
-//! - It is possible to create your own _device_ by implementing the `Device` trait and use the _built-in_ communication protocol.
+//! - It is possible to create your own _device_ by implementing the `Device` trait and use the _built-in_ communication protocol; store a `DeviceContext` and expose it with `Device::context` and `Device::context_mut`, the write path, calibration and state accessors are provided on top.
 //! - You can add your own __communication protocol__ by implementing the `Protocol` trait and use it to drive one of the built-in devices.
+//! - Build and validate the frames of your __communication protocol__ (headers, footers, sequence counters, length prefixes, XOR/sum8/CRC8/CRC16 checksums, padding) with `communication_protocol::framing`.
+//! - Check your __communication protocol__ with `testkit::Conformance` (`testkit` feature), the harness the built-in ones are tested with: no panics, MTU, determinism, plus round-trip and expected vectors when provided.
 //! - Create your own `device` and `communication protocol`.
+//! - Provide a native color temperature command for your __communication protocol__ by overriding `Protocol::white_temperature`; the `Temperature` capability falls back to the blackbody color otherwise.
+//! - Route each command of your __communication protocol__ to a different characteristic (`Protocol::target`) for devices exposing one characteristic per function.
+//! - Declare the white channels of your __communication protocol__ (`RGBW`, `RGBWW`) by overriding `Protocol::channel_layout`; colors are converted to it before encoding, white being extracted from or blended into RGB.
+//! - Mark the native brightness command of your __communication protocol__ with `Protocol::native_brightness`; otherwise `BrightnessOption::Level` scales the last color of the device (`Device::state_cache`).
+//! - Calibrate each device (per-channel gamma, white balance, minimum visible level) with `Device::calibration_mut`, applied to every color before encoding; `CalibrationGuide` builds the profile from the user's comparisons.
+//! - Set the channel order of GRB/BRG/... wired strips per device with `Device::set_channel_order`, applied to every color written (pixels and animations included); `ChannelOrderGuide` identifies it by flashing each channel.
+//! - Emit multi-packet commands by overriding `Protocol::packets`; frames are split according to the MTU of each device `Transport`, with optional pacing between packets. Commands made of several frames (e.g. an animation and its speed) are returned as a list, see `Protocol::hw_animate_frames` and `Protocol::music`.
+//! - Describe a byte-template based __communication protocol__ in a _TOML_ or _JSON_ file and load it at runtime with `DeclarativeProtocol` (`declarative` feature).
+//! - Read back the actual device state with `QueryState::query_state` by implementing `StateProtocol` for your __communication protocol__ (see `GenericRGB`, `Zengge` or `Yeelight`).
+//! - Query what each device was last set to (power, color, brightness, mode, last update, readback confirmation) with `Device::state`; the `LightState` snapshot is serializable with the `serde` feature.
+//! - Use the CSS named colors and curated palettes (sunset, ocean, forest, pastel, holiday sets) of the `palette` module, sample them as gradients, cross-fade through them (`SWAnimate`), or load your own from _TOML_/_JSON_ files (`palette_files` feature).
+//! - List the built-in animations of your __communication protocol__ by overriding `Protocol::hw_animations`, e.g. to populate a mode picker.
+//! - Expose the microphone mode of your __communication protocol__ to `HWMusic` by overriding `Protocol::music`; protocols without one report `BluetoothError::Unsupported`.
+//! - Pass brightness levels, percentages and color temperatures as the validated `Level`, `Percent` and `Kelvin` of the `units` module; checked constructors reject out of range values with a `UnitError`, `saturating` ones clamp them.
 //!
 //! ## Usage
 //!
@@ -97,7 +128,7 @@
 //!     controller.set_all_char(&CharKind::Write, &UuidKind::Uuid16(0xFFD9))?;
 //!
 //!     // Setting first found light color to red
-//!     let first_light = controller.list().get(0).unwrap();
+//!     let first_light = controller.list().first().unwrap();
 //!     first_light.color(&protocol, 255, 0, 0).await?;
 //!
 //!     Ok(())
@@ -169,7 +200,7 @@
 //!         println!("SW Animation - Breathing effect...");
 //!         light
 //!             .breathing(
-//!                 &GenericRGB {},
+//!                 &GenericRGB::default(),
 //!                 &ColorOption::RGB(255, 0, 0),
 //!                 &SWAnimationRepeat::FiniteCount(2),
 //!                 &SWAnimationSpeed::Fastest,
@@ -177,7 +208,7 @@
 //!             .await?;
 //!         light
 //!             .breathing(
-//!                 &GenericRGB {},
+//!                 &GenericRGB::default(),
 //!                 &ColorOption::RGB(0, 255, 0),
 //!                 &SWAnimationRepeat::FiniteCount(2),
 //!                 &SWAnimationSpeed::Fastest,
@@ -185,7 +216,7 @@
 //!             .await?;
 //!         light
 //!             .breathing(
-//!                 &GenericRGB {},
+//!                 &GenericRGB::default(),
 //!                 &ColorOption::RGB(0, 0, 255),
 //!                 &SWAnimationRepeat::FiniteCount(2),
 //!                 &SWAnimationSpeed::Fastest,
@@ -208,10 +239,15 @@
 //!
 //! MIT
 pub mod capability;
+pub mod color;
 pub mod communication_protocol;
 pub mod controller;
 pub mod device;
 pub mod error;
+pub mod palette;
+pub mod units;
+#[cfg(feature = "testkit")]
+pub mod testkit;
 
 //----------//
 // Re-export//
@@ -0,0 +1,294 @@
+//! Named colors and palettes: the CSS named colors, curated palettes
+//! and user-defined ones, sampled as gradients.
+//!
+//! | Palette     | Colors                                   |
+//! |-------------|------------------------------------------|
+//! | `sunset`    | coral, orange, peach, gold               |
+//! | `ocean`     | navy to pale cyan                        |
+//! | `forest`    | dark to light greens                     |
+//! | `pastel`    | soft rainbow                             |
+//! | `christmas` | red, white, green, gold                  |
+//! | `halloween` | pumpkin, purple, slime green             |
+//! | `valentine` | reds and pinks                           |
+//!
+//! Palettes can be loaded from _TOML_ or _JSON_ files (`palette_files`
+//! feature), every color in any notation parsed by `ColorOption`:
+//!
+//! ```toml
+//! name = "campfire"
+//! colors = ["#ff4500", "orange", "hsl(50, 100%, 50%)"]
+//! ```
+//!
+//! ## Examples
+//! ```
+//! use ble_ledly::capability::color::ColorOption;
+//! use ble_ledly::palette::{self, Palette};
+//!
+//! assert_eq!(palette::named("teal"), Some(ColorOption::RGB(0, 128, 128)));
+//!
+//! let fire = Palette::new(
+//!     "fire",
+//!     vec![ColorOption::RGB(255, 0, 0), ColorOption::RGB(255, 255, 0)],
+//! );
+//! assert_eq!(fire.sample(0.5), ColorOption::RGB(255, 128, 0));
+//! // wraps around, back to the first color
+//! assert_eq!(fire.sample_cyclic(1.0), ColorOption::RGB(255, 0, 0));
+//! assert_eq!(fire.gradient(3)[1], ColorOption::RGB(255, 128, 0));
+//!
+//! let ocean = Palette::builtin("ocean").unwrap();
+//! assert_eq!(ocean.colors.len(), 5);
+//! assert_eq!(Palette::builtins().len(), 7);
+//! ```
+#[cfg(feature = "palette_files")]
+use std::{fs, path::Path};
+
+use crate::capability::color::ColorOption;
+use crate::color::CSS_COLORS;
+#[cfg(feature = "palette_files")]
+use crate::error::PaletteError;
+
+/// Color of a CSS name (`teal`, `darkorange`), case insensitive
+pub fn named(name: &str) -> Option<ColorOption> {
+    let name = name.trim().to_ascii_lowercase();
+    CSS_COLORS
+        .binary_search_by(|(css_name, _)| css_name.cmp(&name.as_str()))
+        .ok()
+        .map(|index| ColorOption::from(CSS_COLORS[index].1))
+}
+
+/// Every CSS color name, sorted
+pub fn names() -> impl Iterator<Item = &'static str> {
+    CSS_COLORS.iter().map(|(name, _)| *name)
+}
+
+/// Ordered colors, sampled as a gradient
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<ColorOption>,
+}
+
+// red, green and blue channels
+type Rgb = (u8, u8, u8);
+
+static BUILTINS: [(&str, &[Rgb]); 7] = [
+    (
+        "sunset",
+        &[
+            (255, 94, 98),
+            (255, 126, 95),
+            (254, 180, 123),
+            (255, 195, 113),
+            (249, 212, 35),
+        ],
+    ),
+    (
+        "ocean",
+        &[
+            (3, 4, 94),
+            (0, 119, 182),
+            (0, 180, 216),
+            (144, 224, 239),
+            (202, 240, 248),
+        ],
+    ),
+    (
+        "forest",
+        &[
+            (27, 67, 50),
+            (45, 106, 79),
+            (64, 145, 108),
+            (116, 198, 157),
+            (183, 228, 199),
+        ],
+    ),
+    (
+        "pastel",
+        &[
+            (255, 173, 173),
+            (255, 214, 165),
+            (253, 255, 182),
+            (202, 255, 191),
+            (155, 246, 255),
+            (160, 196, 255),
+            (189, 178, 255),
+            (255, 198, 255),
+        ],
+    ),
+    (
+        "christmas",
+        &[(192, 0, 0), (255, 255, 255), (0, 135, 62), (255, 215, 0)],
+    ),
+    (
+        "halloween",
+        &[(255, 117, 24), (106, 13, 173), (57, 255, 20)],
+    ),
+    (
+        "valentine",
+        &[
+            (255, 10, 84),
+            (255, 71, 126),
+            (255, 112, 150),
+            (255, 133, 161),
+            (251, 177, 189),
+        ],
+    ),
+];
+
+impl Palette {
+    pub fn new(name: &str, colors: Vec<ColorOption>) -> Self {
+        Self {
+            name: name.to_string(),
+            colors,
+        }
+    }
+
+    /// One of the curated palettes, by name
+    pub fn builtin(name: &str) -> Option<Palette> {
+        BUILTINS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(name, colors)| {
+                Palette::new(name, colors.iter().map(|&c| ColorOption::from(c)).collect())
+            })
+    }
+
+    /// Every curated palette
+    pub fn builtins() -> Vec<Palette> {
+        BUILTINS
+            .iter()
+            .filter_map(|(name, _)| Palette::builtin(name))
+            .collect()
+    }
+
+    /// Color at `t` of the gradient from the first to the last
+    /// color, `t` from `0.0` to `1.0`; black if the palette is empty.
+    pub fn sample(&self, t: f32) -> ColorOption {
+        match self.colors.len() {
+            0 => ColorOption::RGB(0, 0, 0),
+            1 => self.colors[0],
+            len => {
+                let position = t.clamp(0.0, 1.0) * (len - 1) as f32;
+                let index = (position as usize).min(len - 2);
+                _lerp(
+                    &self.colors[index],
+                    &self.colors[index + 1],
+                    position - index as f32,
+                )
+            }
+        }
+    }
+
+    /// Color at `t` of the gradient going through every color and back
+    /// to the first one, repeating every `1.0`; for animations.
+    pub fn sample_cyclic(&self, t: f32) -> ColorOption {
+        match self.colors.first() {
+            None => ColorOption::RGB(0, 0, 0),
+            Some(first) => {
+                let mut colors = self.colors.clone();
+                colors.push(*first);
+                Palette::new(&self.name, colors).sample(t.rem_euclid(1.0))
+            }
+        }
+    }
+
+    /// `steps` colors evenly spread over the gradient, e.g. a `Pixels` frame
+    pub fn gradient(&self, steps: usize) -> Vec<ColorOption> {
+        (0..steps)
+            .map(|step| self.sample(step as f32 / (steps.max(2) - 1) as f32))
+            .collect()
+    }
+}
+
+// channel-wise linear interpolation; colors of different
+// layouts are interpolated on their RGB blend
+fn _lerp(from: &ColorOption, to: &ColorOption, t: f32) -> ColorOption {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    match (*from, *to) {
+        (ColorOption::RGBW(r0, g0, b0, w0), ColorOption::RGBW(r1, g1, b1, w1)) => {
+            ColorOption::RGBW(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1), lerp(w0, w1))
+        }
+        (
+            ColorOption::RGBWW(r0, g0, b0, warm0, cold0),
+            ColorOption::RGBWW(r1, g1, b1, warm1, cold1),
+        ) => ColorOption::RGBWW(
+            lerp(r0, r1),
+            lerp(g0, g1),
+            lerp(b0, b1),
+            lerp(warm0, warm1),
+            lerp(cold0, cold1),
+        ),
+        _ => {
+            let ((r0, g0, b0), (r1, g1, b1)) = (from.rgb(), to.rgb());
+            ColorOption::RGB(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+        }
+    }
+}
+
+//-------//
+// Files //
+//-------//
+#[cfg(feature = "palette_files")]
+#[derive(serde::Deserialize)]
+struct PaletteDefinition {
+    name: String,
+    colors: Vec<String>,
+}
+
+#[cfg(feature = "palette_files")]
+impl Palette {
+    /// Loads a palette from a `.toml` or `.json` file
+    pub fn from_file<T: AsRef<Path>>(path: T) -> Result<Self, PaletteError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&source),
+            Some("json") => Self::from_json_str(&source),
+            other => Err(PaletteError::UnsupportedFormat(
+                other.unwrap_or("").to_string(),
+            )),
+        }
+    }
+
+    /// Loads a palette from a _TOML_ document.
+    ///
+    /// ## Examples
+    /// ```
+    /// use ble_ledly::capability::color::ColorOption;
+    /// use ble_ledly::palette::Palette;
+    ///
+    /// let palette = Palette::from_toml_str(
+    ///     r##"
+    ///     name = "campfire"
+    ///     colors = ["#ff4500", "orange", "hsl(50, 100%, 50%)"]
+    ///     "##,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(palette.colors[1], ColorOption::RGB(255, 165, 0));
+    /// assert!(Palette::from_toml_str("name = \"empty\"\ncolors = []").is_err());
+    /// ```
+    pub fn from_toml_str(source: &str) -> Result<Self, PaletteError> {
+        let definition: PaletteDefinition =
+            toml::from_str(source).map_err(|e| PaletteError::Parse(e.to_string()))?;
+        Self::_from_definition(definition)
+    }
+
+    /// Loads a palette from a _JSON_ document.
+    pub fn from_json_str(source: &str) -> Result<Self, PaletteError> {
+        let definition: PaletteDefinition =
+            serde_json::from_str(source).map_err(|e| PaletteError::Parse(e.to_string()))?;
+        Self::_from_definition(definition)
+    }
+
+    fn _from_definition(definition: PaletteDefinition) -> Result<Self, PaletteError> {
+        if definition.colors.is_empty() {
+            return Err(PaletteError::Empty(definition.name));
+        }
+        let colors = definition
+            .colors
+            .iter()
+            .map(|color| color.parse::<ColorOption>())
+            .collect::<Result<Vec<ColorOption>, _>>()?;
+        Ok(Palette::new(&definition.name, colors))
+    }
+}
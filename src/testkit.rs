@@ -0,0 +1,456 @@
+//! Conformance harness for `Protocol` implementations.
+//!
+//! `Conformance` encodes every light, color, brightness and hardware
+//! animation option with the protocol under test and checks that
+//!
+//! - encoding never panics; hardware animations missing from
+//!   `Protocol::hw_animations` are reported as unsupported, not encoded,
+//! - every packet written to the device fits in the MTU (see `Protocol::packets`),
+//! - encoding is deterministic, unless the protocol is stateful,
+//! - frames decode back to the command, when a decoder is provided,
+//! - frames match the expected vectors, when provided.
+//!
+//! Panics are caught, the panic hook is left as is and still prints them.
+//!
+//! ## Examples
+//! ```
+//! use ble_ledly::capability::color::ColorOption;
+//! use ble_ledly::capability::light::LightOption;
+//! use ble_ledly::communication_protocol::GenericRGB;
+//! use ble_ledly::testkit::{Command, Conformance};
+//!
+//! let protocol = GenericRGB::default();
+//!
+//! Conformance::new(&protocol)
+//!     .mtu(20)
+//!     .decoder(|frame| match frame {
+//!         [0xCC, 0x23, 0x33] => Some(Command::Light(LightOption::On)),
+//!         [0xCC, 0x24, 0x33] => Some(Command::Light(LightOption::Off)),
+//!         [0x56, r, g, b, 0x00, 0xF0, 0xAA] => Some(Command::Color(ColorOption::RGB(*r, *g, *b))),
+//!         _ => None,
+//!     })
+//!     // hardware animations are not decoded
+//!     .skip_roundtrip(|command| matches!(command, Command::HWAnimate(_)))
+//!     .expect(Command::Light(LightOption::On), &[0xCC, 0x23, 0x33])
+//!     .run()
+//!     .assert_conformant();
+//! ```
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use crate::capability::{
+    brightness::BrightnessOption,
+    color::ColorOption,
+    hw_animate::{HWAnimateOption, HWAnimationSpeedSetting},
+    light::LightOption,
+};
+use crate::communication_protocol::Protocol;
+use crate::units::Level;
+
+// tolerance on decoded brightness levels, covers 100-step encodings
+const LEVEL_TOLERANCE: f32 = 0.01;
+
+const COLORS: [ColorOption; 7] = [
+    ColorOption::RGB(0, 0, 0),
+    ColorOption::RGB(255, 255, 255),
+    ColorOption::RGB(255, 0, 0),
+    ColorOption::RGB(0, 255, 0),
+    ColorOption::RGB(0, 0, 255),
+    ColorOption::RGB(255, 128, 0),
+    ColorOption::RGB(1, 2, 3),
+];
+const LEVELS: [f32; 4] = [0.0, 0.01, 0.5, 1.0];
+// borrowed by the 'static `HWAnimateOption` samples
+static CONTINUOUS_SPEEDS: [HWAnimationSpeedSetting; 3] = [
+    HWAnimationSpeedSetting::Level(Level::OFF),
+    HWAnimationSpeedSetting::Level(Level::FULL),
+    HWAnimationSpeedSetting::Period(Duration::from_secs(1)),
+];
+
+/// Command encoded by a protocol, owned counterpart
+/// of the options taken by the `Protocol` methods.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Light(LightOption),
+    Color(ColorOption),
+    /// `BrightnessOption::Level`
+    Brightness(Level),
+    /// `BrightnessOption::LevelWithColor`
+    BrightnessWithColor(Level, ColorOption),
+    HWAnimate(HWAnimateOption<'static>),
+}
+
+/// Reason a command failed the conformance checks
+#[derive(Clone, Debug, PartialEq)]
+pub enum Failure {
+    /// Encoding panicked with the given message
+    Panicked(String),
+    /// A packet is longer than the MTU
+    ExceedsMtu { packet: Vec<u8>, mtu: usize },
+    /// Encoding the same command twice gave different frames
+    NonDeterministic { first: Vec<u8>, second: Vec<u8> },
+    /// The frame decoded to another command, `None` if not decoded at all
+    RoundTrip {
+        frame: Vec<u8>,
+        decoded: Option<Command>,
+    },
+    /// The frame differs from the expected vector
+    Mismatch { expected: Vec<u8>, found: Vec<u8> },
+}
+
+/// Outcome of `Conformance::run`
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Commands passing every check
+    pub passed: Vec<Command>,
+    /// Hardware animations not listed by `Protocol::hw_animations`
+    pub unsupported: Vec<Command>,
+    pub failures: Vec<(Command, Failure)>,
+}
+
+type Decoder<'p> = Box<dyn Fn(&[u8]) -> Option<Command> + 'p>;
+type Filter<'p> = Box<dyn Fn(&Command) -> bool + 'p>;
+
+/// Conformance checks of a protocol, see the module documentation.
+pub struct Conformance<'p, P: Protocol> {
+    protocol: &'p P,
+    mtu: Option<usize>,
+    deterministic: bool,
+    decoder: Option<Decoder<'p>>,
+    skip_roundtrip: Option<Filter<'p>>,
+    expected: Vec<(Command, Vec<u8>)>,
+}
+
+impl Command {
+    /// Every command checked by `Conformance`
+    pub fn samples() -> Vec<Command> {
+        let mut commands = vec![
+            Command::Light(LightOption::On),
+            Command::Light(LightOption::Off),
+        ];
+        commands.extend(COLORS.iter().map(|color| Command::Color(*color)));
+        let levels = LEVELS.map(Level::saturating);
+        commands.extend(levels.iter().map(|level| Command::Brightness(*level)));
+        for level in levels.iter() {
+            commands.extend(
+                COLORS
+                    .iter()
+                    .map(|color| Command::BrightnessWithColor(*level, *color)),
+            );
+        }
+        for speed in HWAnimationSpeedSetting::all()
+            .iter()
+            .chain(CONTINUOUS_SPEEDS.iter())
+        {
+            commands.extend(
+                HWAnimateOption::all(speed)
+                    .into_iter()
+                    .map(Command::HWAnimate),
+            );
+        }
+        commands
+    }
+
+    /// Encodes the command with `protocol`
+    pub fn encode<P: Protocol>(&self, protocol: &P) -> Vec<u8> {
+        match self {
+            Command::Light(option) => protocol.light(option),
+            Command::Color(option) => protocol.color(option),
+            Command::Brightness(level) => protocol.brightness(&BrightnessOption::Level(*level)),
+            Command::BrightnessWithColor(level, color) => {
+                protocol.brightness(&BrightnessOption::LevelWithColor(*level, color))
+            }
+            Command::HWAnimate(option) => protocol.hw_animate(option),
+        }
+    }
+
+    // Whether `decoded` is an acceptable decoding of the command: levels are
+    // quantized, and a level may be encoded as the scaled color (white if none).
+    fn _matches(&self, decoded: &Command) -> bool {
+        let close = |a: Level, b: Level| (a.get() - b.get()).abs() <= LEVEL_TOLERANCE;
+        match (self, decoded) {
+            (Command::Brightness(sent), Command::Brightness(found)) => close(*sent, *found),
+            (
+                Command::BrightnessWithColor(sent_level, sent_color),
+                Command::BrightnessWithColor(found_level, found_color),
+            ) => close(*sent_level, *found_level) && sent_color == found_color,
+            (Command::Brightness(level), Command::Color(_)) => {
+                Command::BrightnessWithColor(*level, ColorOption::RGB(255, 255, 255))
+                    ._matches(decoded)
+            }
+            (
+                Command::BrightnessWithColor(level, ColorOption::RGB(r, g, b)),
+                Command::Color(ColorOption::RGB(fr, fg, fb)),
+            ) => [(r, fr), (g, fg), (b, fb)].iter().all(|(sent, found)| {
+                ((**sent as f32 * level.get()) as i16 - **found as i16).abs() <= 1
+            }),
+            _ => self == decoded,
+        }
+    }
+}
+
+impl Report {
+    pub fn is_conformant(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// # Panics
+    ///
+    /// Panics listing the failures, if any.
+    pub fn assert_conformant(&self) {
+        assert!(self.is_conformant(), "{}", self);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} passed, {} unsupported, {} failed",
+            self.passed.len(),
+            self.unsupported.len(),
+            self.failures.len()
+        )?;
+        for (command, failure) in self.failures.iter() {
+            writeln!(f, "  {:?}: {:?}", command, failure)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'p, P: Protocol> Conformance<'p, P> {
+    pub fn new(protocol: &'p P) -> Self {
+        Self {
+            protocol,
+            mtu: None,
+            deterministic: true,
+            decoder: None,
+            skip_roundtrip: None,
+            expected: Vec::new(),
+        }
+    }
+
+    /// Checks the packets of every frame against the given MTU
+    pub fn mtu(mut self, mtu: usize) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+    /// Skips the determinism check, for protocols embedding
+    /// sequence numbers or random data in their frames
+    pub fn stateful(mut self) -> Self {
+        self.deterministic = false;
+        self
+    }
+    /// Decodes frames back into the command they encode
+    pub fn decoder<F: Fn(&[u8]) -> Option<Command> + 'p>(mut self, decoder: F) -> Self {
+        self.decoder = Some(Box::new(decoder));
+        self
+    }
+    /// Excludes commands from the round-trip check,
+    /// for encodings that cannot be decoded unambiguously
+    pub fn skip_roundtrip<F: Fn(&Command) -> bool + 'p>(mut self, filter: F) -> Self {
+        self.skip_roundtrip = Some(Box::new(filter));
+        self
+    }
+    /// Expected frame of a command; the command is checked
+    /// in addition to the samples, if not among them
+    pub fn expect(mut self, command: Command, frame: &[u8]) -> Self {
+        self.expected.push((command, frame.to_vec()));
+        self
+    }
+
+    /// Runs the checks over `Command::samples` and the expected vectors
+    pub fn run(&self) -> Report {
+        let mut commands = Command::samples();
+        for (command, _) in self.expected.iter() {
+            if !commands.contains(command) {
+                commands.push(*command);
+            }
+        }
+
+        let mut report = Report::default();
+        for command in commands {
+            match self._check(&command) {
+                Ok(true) => report.passed.push(command),
+                Ok(false) => report.unsupported.push(command),
+                Err(failure) => report.failures.push((command, failure)),
+            }
+        }
+        report
+    }
+
+    // Ok(false) if the command is not supported
+    fn _check(&self, command: &Command) -> Result<bool, Failure> {
+        if let Command::HWAnimate(option) = command {
+            if !self.protocol.hw_animations(option.speed()).contains(option) {
+                return Ok(false);
+            }
+        }
+        let frame = self._encode(command).map_err(Failure::Panicked)?;
+
+        if let Some(mtu) = self.mtu {
+            if let Some(packet) = self
+                .protocol
+                .packets(&frame, mtu)
+                .into_iter()
+                .find(|packet| packet.len() > mtu)
+            {
+                return Err(Failure::ExceedsMtu { packet, mtu });
+            }
+        }
+
+        if self.deterministic {
+            let second = self._encode(command).map_err(Failure::Panicked)?;
+            if second != frame {
+                return Err(Failure::NonDeterministic {
+                    first: frame,
+                    second,
+                });
+            }
+        }
+
+        let skipped = self
+            .skip_roundtrip
+            .as_ref()
+            .is_some_and(|skip| skip(command));
+        if let (Some(decoder), false) = (self.decoder.as_ref(), skipped) {
+            let decoded = decoder(&frame);
+            if !decoded.is_some_and(|decoded| command._matches(&decoded)) {
+                return Err(Failure::RoundTrip { frame, decoded });
+            }
+        }
+
+        if let Some((_, expected)) = self.expected.iter().find(|(c, _)| c == command) {
+            if *expected != frame {
+                return Err(Failure::Mismatch {
+                    expected: expected.clone(),
+                    found: frame,
+                });
+            }
+        }
+        Ok(true)
+    }
+
+    // Err with the message if encoding panicked
+    fn _encode(&self, command: &Command) -> Result<Vec<u8>, String> {
+        panic::catch_unwind(AssertUnwindSafe(|| command.encode(self.protocol))).map_err(|payload| {
+            payload
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<&str>().map(|m| m.to_string()))
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::color::ChannelLayout;
+    use crate::communication_protocol::{ElkBledom, GenericRGB, Govee, HueBle, Yeelight, Zengge};
+
+    // BLE 4.0 MTU, the smallest the controllers negotiate
+    const MTU: usize = 20;
+
+    #[test]
+    fn built_in_protocols_are_conformant() {
+        for layout in [
+            ChannelLayout::RGB,
+            ChannelLayout::RGBW,
+            ChannelLayout::RGBWW,
+        ] {
+            Conformance::new(&GenericRGB::with_layout(layout))
+                .mtu(MTU)
+                .run()
+                .assert_conformant();
+            // frames carry a sequence number
+            Conformance::new(&Zengge::with_layout(layout))
+                .mtu(MTU)
+                .stateful()
+                .run()
+                .assert_conformant();
+        }
+        Conformance::new(&ElkBledom::default())
+            .mtu(MTU)
+            .run()
+            .assert_conformant();
+        Conformance::new(&Govee::default())
+            .mtu(MTU)
+            .run()
+            .assert_conformant();
+        Conformance::new(&HueBle::default())
+            .mtu(MTU)
+            .run()
+            .assert_conformant();
+        Conformance::new(&Yeelight::default())
+            .mtu(MTU)
+            .run()
+            .assert_conformant();
+    }
+
+    // GenericRGB, without brightness command
+    struct Unfinished;
+
+    impl Protocol for Unfinished {
+        fn light(&self, option: &LightOption) -> Vec<u8> {
+            GenericRGB::default().light(option)
+        }
+        fn color(&self, option: &ColorOption) -> Vec<u8> {
+            GenericRGB::default().color(option)
+        }
+        fn brightness(&self, _option: &BrightnessOption) -> Vec<u8> {
+            unimplemented!("Brightness")
+        }
+        fn hw_animate(&self, option: &HWAnimateOption) -> Vec<u8> {
+            GenericRGB::default().hw_animate(option)
+        }
+        fn hw_animations<'e>(
+            &self,
+            speed: &'e HWAnimationSpeedSetting,
+        ) -> Vec<HWAnimateOption<'e>> {
+            GenericRGB::default().hw_animations(speed)
+        }
+        fn _animation_speed(setting: &HWAnimationSpeedSetting) -> u8 {
+            GenericRGB::_animation_speed(setting)
+        }
+        fn _static_color(color: &crate::capability::hw_animate::HWStaticColorOption) -> u8 {
+            GenericRGB::_static_color(color)
+        }
+    }
+
+    #[test]
+    fn reports_unimplemented_commands_as_failures() {
+        let report = Conformance::new(&Unfinished).mtu(MTU).run();
+
+        assert!(!report.is_conformant());
+        assert!(report.unsupported.is_empty());
+        assert!(report.failures.iter().all(|(command, failure)| {
+            matches!(
+                command,
+                Command::Brightness(_) | Command::BrightnessWithColor(..)
+            ) && *failure == Failure::Panicked("not implemented: Brightness".to_string())
+        }));
+    }
+
+    #[test]
+    fn reports_unlisted_animations_as_unsupported() {
+        let report = Conformance::new(&Govee::default()).run();
+
+        assert!(report.is_conformant());
+        assert!(!report.unsupported.is_empty());
+        assert!(report
+            .unsupported
+            .iter()
+            .all(|command| matches!(command, Command::HWAnimate(_))));
+    }
+
+    #[cfg(feature = "pixels")]
+    #[test]
+    fn sp110e_is_conformant() {
+        Conformance::new(&crate::communication_protocol::Sp110e::default())
+            .mtu(MTU)
+            .run()
+            .assert_conformant();
+    }
+}
@@ -0,0 +1,205 @@
+//! Validated units accepted by the capabilities: brightness `Level`s,
+//! `Percent`ages and color temperatures in `Kelvin`.
+//!
+//! Checked constructors reject out of range values with a `UnitError`,
+//! `saturating` ones clamp them; values deserialized with the `serde`
+//! feature are checked as well.
+//!
+//! ## Examples
+//! ```
+//! use ble_ledly::error::UnitError;
+//! use ble_ledly::units::{Kelvin, Level, Percent};
+//!
+//! let half = Level::new(0.5).unwrap();
+//! assert_eq!(half.get(), 0.5);
+//! assert_eq!(Level::new(1.5), Err(UnitError::InvalidLevel(1.5)));
+//! assert!(Level::new(f32::NAN).is_err());
+//! assert_eq!(Level::saturating(1.5), Level::FULL);
+//!
+//! let percent = Percent::new(25).unwrap();
+//! assert_eq!(Level::from(percent).get(), 0.25);
+//! assert_eq!(Percent::from(half).get(), 50);
+//! assert_eq!(Percent::new(120), Err(UnitError::InvalidPercent(120)));
+//!
+//! assert_eq!(Kelvin::new(2700).unwrap().get(), 2700);
+//! assert_eq!(Kelvin::new(100), Err(UnitError::InvalidKelvin(100)));
+//! assert_eq!(Kelvin::saturating(100), Kelvin::MIN);
+//! ```
+use std::fmt;
+
+use crate::error::UnitError;
+
+/// Level from `0.0` (off) to `1.0` (full)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "f32", into = "f32")
+)]
+pub struct Level(f32);
+
+/// Percentage from `0` to `100`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
+pub struct Percent(u8);
+
+/// Color temperature of a white light, from `1000` to `40000` Kelvin
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u16", into = "u16")
+)]
+pub struct Kelvin(u16);
+
+//-------//
+// Level //
+//-------//
+impl Level {
+    pub const OFF: Level = Level(0.0);
+    pub const FULL: Level = Level(1.0);
+
+    pub fn new(level: f32) -> Result<Level, UnitError> {
+        if (0.0..=1.0).contains(&level) {
+            Ok(Level(level))
+        } else {
+            Err(UnitError::InvalidLevel(level))
+        }
+    }
+    /// Clamped to `0.0..=1.0`, `NaN` being off
+    pub fn saturating(level: f32) -> Level {
+        if level.is_nan() {
+            Level::OFF
+        } else {
+            Level(level.clamp(0.0, 1.0))
+        }
+    }
+    pub fn get(&self) -> f32 {
+        self.0
+    }
+}
+
+impl TryFrom<f32> for Level {
+    type Error = UnitError;
+
+    fn try_from(level: f32) -> Result<Self, Self::Error> {
+        Level::new(level)
+    }
+}
+
+impl From<Level> for f32 {
+    fn from(level: Level) -> Self {
+        level.0
+    }
+}
+
+impl From<Percent> for Level {
+    fn from(percent: Percent) -> Self {
+        Level(percent.0 as f32 / 100.0)
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+//---------//
+// Percent //
+//---------//
+impl Percent {
+    pub fn new(percent: u8) -> Result<Percent, UnitError> {
+        if percent <= 100 {
+            Ok(Percent(percent))
+        } else {
+            Err(UnitError::InvalidPercent(percent))
+        }
+    }
+    /// Clamped to `0..=100`
+    pub fn saturating(percent: u8) -> Percent {
+        Percent(percent.min(100))
+    }
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for Percent {
+    type Error = UnitError;
+
+    fn try_from(percent: u8) -> Result<Self, Self::Error> {
+        Percent::new(percent)
+    }
+}
+
+impl From<Percent> for u8 {
+    fn from(percent: Percent) -> Self {
+        percent.0
+    }
+}
+
+impl From<Level> for Percent {
+    fn from(level: Level) -> Self {
+        Percent((level.0 * 100.0).round() as u8)
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+//--------//
+// Kelvin //
+//--------//
+impl Kelvin {
+    pub const MIN: Kelvin = Kelvin(1000);
+    pub const MAX: Kelvin = Kelvin(40000);
+
+    pub fn new(kelvin: u16) -> Result<Kelvin, UnitError> {
+        if (Kelvin::MIN.0..=Kelvin::MAX.0).contains(&kelvin) {
+            Ok(Kelvin(kelvin))
+        } else {
+            Err(UnitError::InvalidKelvin(kelvin))
+        }
+    }
+    /// Clamped to `1000..=40000`
+    pub const fn saturating(kelvin: u16) -> Kelvin {
+        if kelvin < Kelvin::MIN.0 {
+            Kelvin::MIN
+        } else if kelvin > Kelvin::MAX.0 {
+            Kelvin::MAX
+        } else {
+            Kelvin(kelvin)
+        }
+    }
+    pub fn get(&self) -> u16 {
+        self.0
+    }
+}
+
+impl TryFrom<u16> for Kelvin {
+    type Error = UnitError;
+
+    fn try_from(kelvin: u16) -> Result<Self, Self::Error> {
+        Kelvin::new(kelvin)
+    }
+}
+
+impl From<Kelvin> for u16 {
+    fn from(kelvin: Kelvin) -> Self {
+        kelvin.0
+    }
+}
+
+impl fmt::Display for Kelvin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}K", self.0)
+    }
+}